@@ -0,0 +1,133 @@
+/// `# vhdt: path=/healthz` comments are kept by nginx and apache parsers as directives
+/// with this name and `key=value` args.
+pub const ANNOTATION_DIRECTIVE: &str = "#vhdt";
+
+const ANNOTATION_PREFIX: &str = "vhdt:";
+
+const IGNORE_ANNOTATION: &str = "ignore";
+const NAME_ANNOTATION_KEY: &str = "name";
+const EXPECT_ANNOTATION_KEY: &str = "expect";
+const TAGS_ANNOTATION_KEY: &str = "tags";
+const PATH_ANNOTATION_KEY: &str = "path";
+
+/// Settings from `# vhdt:` comments of nginx `server` block or apache `<VirtualHost>`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotations {
+    /// `# vhdt: ignore` - skip vhost
+    pub ignore: bool,
+
+    /// `# vhdt: name="Billing API"`
+    pub name: Option<String>,
+
+    /// `# vhdt: expect=401`
+    pub expected_status: Option<u16>,
+
+    /// `# vhdt: tags=team:payments,env:prod`
+    pub tags: Vec<String>,
+
+    /// `# vhdt: path=/healthz,/api/status`
+    pub paths: Vec<String>
+}
+
+/// Returns annotation args for comment text without `#`, `None` for regular comments.
+/// Values with spaces are quoted: `name="Billing API"`.
+pub fn parse_annotation(comment: &str) -> Option<Vec<String>> {
+    let args = comment.trim().strip_prefix(ANNOTATION_PREFIX)?;
+
+    let mut result: Vec<String> = Vec::new();
+    let mut arg = String::new();
+    let mut quoted = false;
+
+    for c in args.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            _ if c.is_whitespace() && !quoted => {
+                if !arg.is_empty() {
+                    result.push(std::mem::take(&mut arg));
+                }
+            }
+            _ => arg.push(c)
+        }
+    }
+
+    if !arg.is_empty() {
+        result.push(arg);
+    }
+
+    Some(result)
+}
+
+/// Merges args of all annotations of the block, comma separated values are split.
+pub fn get_annotations<'a>(annotations: impl IntoIterator<Item = &'a [String]>) -> Annotations {
+    let mut result = Annotations::default();
+
+    for arg in annotations.into_iter().flatten() {
+        match arg.split_once('=') {
+            None if arg == IGNORE_ANNOTATION => result.ignore = true,
+
+            Some((NAME_ANNOTATION_KEY, name)) if !name.is_empty() => result.name = Some(name.to_string()),
+
+            Some((EXPECT_ANNOTATION_KEY, status)) => match status.parse::<u16>() {
+                Ok(status) if (100..=599).contains(&status) => result.expected_status = Some(status),
+                _ => warn!("invalid expected status annotation '{arg}', skip")
+            }
+
+            Some((TAGS_ANNOTATION_KEY, tags)) => result.tags.extend(split_values(tags)),
+
+            Some((PATH_ANNOTATION_KEY, paths)) => {
+                for path in split_values(paths) {
+                    if !path.starts_with('/') {
+                        warn!("invalid monitoring path annotation '{path}', skip");
+                    } else if !result.paths.contains(&path) {
+                        result.paths.push(path);
+                    }
+                }
+            }
+
+            _ => warn!("unsupported annotation '{arg}', skip")
+        }
+    }
+
+    result
+}
+
+fn split_values(values: &str) -> Vec<String> {
+    values.split(',')
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod annotation_tests {
+    use crate::annotation::{Annotations, get_annotations, parse_annotation};
+
+    #[test]
+    fn annotation_args_should_be_parsed_from_comment() {
+        assert_eq!(parse_annotation(" vhdt: ignore name=\"Billing API\" path=/healthz").unwrap(),
+                   vec!["ignore", "name=Billing API", "path=/healthz"]);
+
+        assert_eq!(parse_annotation(" listen 81;"), None);
+    }
+
+    #[test]
+    fn annotations_of_block_should_be_merged() {
+        let args1 = parse_annotation("vhdt: path=/healthz,/api/status name=Billing").unwrap();
+        let args2 = parse_annotation("vhdt: tags=team:payments,env:prod expect=401 path=/healthz").unwrap();
+        let args3 = parse_annotation("vhdt: expect=abc path=healthz unknown=1").unwrap();
+
+        let annotations = get_annotations([args1.as_slice(), args2.as_slice(), args3.as_slice()]);
+
+        assert_eq!(annotations, Annotations {
+            ignore: false,
+            name: Some("Billing".to_string()),
+            expected_status: Some(401),
+            tags: vec!["team:payments".to_string(), "env:prod".to_string()],
+            paths: vec!["/healthz".to_string(), "/api/status".to_string()]
+        });
+
+        let ignore_args = parse_annotation("vhdt: ignore").unwrap();
+        assert!(get_annotations([ignore_args.as_slice()]).ignore);
+    }
+}
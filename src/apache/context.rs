@@ -0,0 +1,227 @@
+use crate::apache::macros::{MACRO_SECTION, Macros, UNDEF_MACRO_DIRECTIVE, USE_DIRECTIVE};
+use crate::apache::parser::Directive;
+use crate::apache::variables::Variables;
+
+const LOAD_MODULE_DIRECTIVE: &str = "LoadModule";
+const IF_MODULE_SECTION: &str = "IfModule";
+const IF_DEFINE_SECTION: &str = "IfDefine";
+
+/// Modules compiled into apache, they don't have `LoadModule` directives.
+const BUILT_IN_MODULES: [&str; 8] = [
+    "core_module", "http_core_module", "so_module", "log_config_module", "logio_module",
+    "version_module", "unixd_module", "watchdog_module"
+];
+
+/// Result of directive evaluation.
+pub enum Evaluation {
+    Keep,
+
+    /// Inactive conditional section or macro definition
+    Skip,
+
+    /// Expanded macro, directives must be evaluated too
+    Replace(Vec<Directive>)
+}
+
+/// Config reading state: variables, macros and loaded modules.
+pub struct ConfigContext {
+    pub variables: Variables,
+
+    macros: Macros,
+
+    /// Module names in `LoadModule` format: `ssl_module`
+    modules: Vec<String>,
+
+    /// Modules are known if config has `LoadModule` directives or modules were supplied by user
+    modules_known: bool
+}
+
+impl ConfigContext {
+    /// `modules` - loaded modules supplied by user (`apachectl -M`),
+    /// both `ssl_module` and `mod_ssl.c` formats are supported.
+    pub fn new(variables: Variables, modules: &[String]) -> ConfigContext {
+        ConfigContext {
+            variables,
+            macros: Macros::default(),
+            modules: modules.iter().map(|module| get_module_name(module)).collect(),
+            modules_known: !modules.is_empty()
+        }
+    }
+
+    /// Substitutes variables, tracks `Define`, `LoadModule` directives and macros.
+    ///
+    /// Inactive `<IfModule>` / `<IfDefine>` sections must be skipped with their content,
+    /// `Use` is replaced with macro body.
+    pub fn apply(&mut self, directive: &mut Directive) -> Evaluation {
+        // macro body is substituted on use, variables inside it as well
+        if directive.is(MACRO_SECTION) && directive.is_block() {
+            self.macros.define(directive);
+            return Evaluation::Skip;
+        }
+
+        self.variables.apply(directive);
+
+        if directive.is_block() {
+            return if self.is_section_active(directive) { Evaluation::Keep } else { Evaluation::Skip };
+        }
+
+        if directive.is(USE_DIRECTIVE) {
+            return Evaluation::Replace(self.macros.expand(directive));
+        }
+
+        if directive.is(UNDEF_MACRO_DIRECTIVE) {
+            if let Some(name) = directive.args.first() {
+                self.macros.undefine(name);
+            }
+        }
+
+        if directive.is(LOAD_MODULE_DIRECTIVE) {
+            if let Some(module) = directive.args.first() {
+                trace!("module loaded '{module}'");
+                self.modules.push(get_module_name(module));
+                self.modules_known = true;
+            }
+        }
+
+        Evaluation::Keep
+    }
+
+    /// `<IfModule>` is active when modules are unknown (e.g. vhost file without main config).
+    fn is_section_active(&self, section: &Directive) -> bool {
+        let condition = match section.args.first() {
+            Some(condition) => condition,
+            None => return true
+        };
+
+        let (negative, name) = match condition.strip_prefix('!') {
+            Some(name) => (true, name),
+            None => (false, condition.as_str())
+        };
+
+        let active = if section.is(IF_MODULE_SECTION) {
+            !self.modules_known || self.is_module_loaded(name) != negative
+
+        } else if section.is(IF_DEFINE_SECTION) {
+            self.variables.is_defined(name) != negative
+
+        } else {
+            true
+        };
+
+        if !active {
+            debug!("section <{} {}> at line {} is inactive, skip", section.name, condition, section.line);
+        }
+
+        active
+    }
+
+    fn is_module_loaded(&self, name: &str) -> bool {
+        let module_name = get_module_name(name);
+
+        BUILT_IN_MODULES.contains(&module_name.as_str()) ||
+            self.modules.iter().any(|module| module == &module_name)
+    }
+}
+
+/// Evaluates config in order: substitutes variables, expands macros and removes inactive
+/// conditional sections.
+pub fn evaluate_config(directives: Vec<Directive>, context: &mut ConfigContext) -> Vec<Directive> {
+    let mut results: Vec<Directive> = Vec::new();
+
+    for mut directive in directives {
+        match context.apply(&mut directive) {
+            Evaluation::Keep => {}
+            Evaluation::Skip => continue,
+            Evaluation::Replace(expanded) => {
+                results.append(&mut evaluate_config(expanded, context));
+                continue;
+            }
+        }
+
+        if let Some(children) = directive.block.take() {
+            directive.block = Some(evaluate_config(children, context));
+        }
+
+        results.push(directive);
+    }
+
+    results
+}
+
+/// `mod_ssl.c` -> `ssl_module`, `ssl_module` stays as is
+fn get_module_name(value: &str) -> String {
+    match value.strip_suffix(".c") {
+        Some(file_name) => {
+            let name = file_name.strip_prefix("mod_").unwrap_or(file_name);
+            format!("{name}_module")
+        }
+        None => value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod context_tests {
+    use crate::apache::context::{ConfigContext, evaluate_config};
+    use crate::apache::parser::{Directive, parse_config};
+    use crate::apache::variables::Variables;
+
+    fn get_server_names(directives: &[Directive]) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+
+        for directive in directives {
+            if directive.is("ServerName") {
+                names.push(directive.args[0].to_string());
+
+            } else {
+                names.append(&mut get_server_names(directive.children()));
+            }
+        }
+
+        names
+    }
+
+    fn get_active_server_names(input: &str, modules: &[String]) -> Vec<String> {
+        let mut context = ConfigContext::new(Variables::default(), modules);
+        get_server_names(&evaluate_config(parse_config(input).unwrap(), &mut context))
+    }
+
+    #[test]
+    fn if_module_should_be_evaluated_with_load_module_directives() {
+        let input = "LoadModule ssl_module modules/mod_ssl.so\n\
+                     <IfModule mod_ssl.c>\n    ServerName ssl.example.com\n</IfModule>\n\
+                     <IfModule rewrite_module>\n    ServerName rewrite.example.com\n</IfModule>\n\
+                     <IfModule !mod_rewrite.c>\n    ServerName no-rewrite.example.com\n</IfModule>\n\
+                     <IfModule log_config_module>\n    ServerName built-in.example.com\n</IfModule>";
+
+        assert_eq!(get_active_server_names(input, &[]),
+                   vec!["ssl.example.com", "no-rewrite.example.com", "built-in.example.com"]);
+    }
+
+    #[test]
+    fn if_module_should_be_active_if_modules_are_unknown() {
+        let input = "<IfModule mod_ssl.c>\n    ServerName ssl.example.com\n</IfModule>";
+
+        assert_eq!(get_active_server_names(input, &[]), vec!["ssl.example.com"]);
+        assert!(get_active_server_names(input, &["rewrite_module".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn if_define_should_be_evaluated_with_define_directives() {
+        let input = "Define PROD\n\
+                     <IfDefine PROD>\n    ServerName prod.example.com\n</IfDefine>\n\
+                     <IfDefine !PROD>\n    ServerName dev.example.com\n</IfDefine>\n\
+                     <IfDefine !SSL>\n    Define SSL\n    ServerName no-ssl.example.com\n</IfDefine>\n\
+                     <IfDefine SSL>\n    ServerName ssl.example.com\n</IfDefine>";
+
+        assert_eq!(get_active_server_names(input, &[]),
+                   vec!["prod.example.com", "no-ssl.example.com", "ssl.example.com"]);
+    }
+
+    #[test]
+    fn directives_inside_inactive_sections_should_be_ignored() {
+        let input = "<IfDefine DEBUG>\n    LoadModule ssl_module modules/mod_ssl.so\n</IfDefine>\n\
+                     <IfModule ssl_module>\n    ServerName ssl.example.com\n</IfModule>";
+
+        assert_eq!(get_active_server_names(input, &["so_module".to_string()]), Vec::<String>::new());
+    }
+}
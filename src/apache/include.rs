@@ -0,0 +1,245 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use glob::glob;
+
+use crate::apache::parser::{Directive, parse_config_file};
+use crate::apache::context::{ConfigContext, Evaluation};
+
+const INCLUDE_DIRECTIVE: &str = "Include";
+const INCLUDE_OPTIONAL_DIRECTIVE: &str = "IncludeOptional";
+const SERVER_ROOT_DIRECTIVE: &str = "ServerRoot";
+
+/// Loads config starting from the main file (`/etc/httpd/conf/httpd.conf`,
+/// `/etc/apache2/apache2.conf`) and replaces `Include` / `IncludeOptional` directives
+/// with directives from included files, like apache does.
+///
+/// Relative include paths are resolved from `ServerRoot`, the main config directory is used
+/// until `ServerRoot` is set. Config is evaluated in order (variables, conditional sections,
+/// macros), so `Define`, `LoadModule` and `<Macro>` affect included files too.
+pub fn load_config_with_includes(root_config: &Path,
+                                 context: ConfigContext) -> anyhow::Result<Vec<Directive>> {
+    let mut loader = ConfigLoader {
+        server_root: root_config.parent().unwrap_or(Path::new("")).to_path_buf(),
+        context,
+        loading_files: Vec::new()
+    };
+
+    loader.load_config_file(root_config)
+}
+
+struct ConfigLoader {
+    server_root: PathBuf,
+
+    context: ConfigContext,
+
+    /// Include stack for cycle detection
+    loading_files: Vec<PathBuf>
+}
+
+impl ConfigLoader {
+    fn load_config_file(&mut self, config_file: &Path) -> anyhow::Result<Vec<Directive>> {
+        let canonical_path = config_file.canonicalize()
+                                        .unwrap_or_else(|_| config_file.to_path_buf());
+
+        if self.loading_files.contains(&canonical_path) {
+            return Err(anyhow!("include cycle detected for file '{}'", config_file.display()));
+        }
+
+        debug!("load config file '{}'", config_file.display());
+
+        self.loading_files.push(canonical_path);
+
+        let result = parse_config_file(config_file)
+            .and_then(|directives| self.resolve_includes(directives));
+
+        self.loading_files.pop();
+
+        result
+    }
+
+    fn resolve_includes(&mut self, directives: Vec<Directive>) -> anyhow::Result<Vec<Directive>> {
+        let mut results: Vec<Directive> = Vec::new();
+
+        for mut directive in directives {
+            match self.context.apply(&mut directive) {
+                Evaluation::Keep => {}
+                Evaluation::Skip => continue,
+                Evaluation::Replace(expanded) => {
+                    results.append(&mut self.resolve_includes(expanded)?);
+                    continue;
+                }
+            }
+
+            let optional = directive.is(INCLUDE_OPTIONAL_DIRECTIVE);
+
+            if (optional || directive.is(INCLUDE_DIRECTIVE)) && !directive.is_block() {
+                let include_path = directive.args.first().ok_or_else(
+                    || anyhow!("{} without path at line {}", directive.name, directive.line))?;
+
+                for included_file in self.get_included_files(include_path, optional)? {
+                    let mut included_directives = self.load_config_file(&included_file)?;
+                    results.append(&mut included_directives);
+                }
+
+            } else {
+                if directive.is(SERVER_ROOT_DIRECTIVE) {
+                    if let Some(server_root) = directive.args.first() {
+                        debug!("server root '{server_root}'");
+                        self.server_root = PathBuf::from(server_root);
+                    }
+                }
+
+                if let Some(children) = directive.block.take() {
+                    directive.block = Some(self.resolve_includes(children)?);
+                }
+
+                results.push(directive);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// `Include` fails for missing files and patterns without matches, `IncludeOptional`
+    /// ignores them. Directories are included with all files inside (recursively).
+    fn get_included_files(&self, include_path: &str, optional: bool) -> anyhow::Result<Vec<PathBuf>> {
+        let path = self.server_root.join(include_path);
+
+        let paths = if is_glob_pattern(include_path) {
+            let path_pattern = path.to_str().ok_or_else(
+                || anyhow!("unsupported include path '{}'", path.display()))?;
+
+            let mut paths: Vec<PathBuf> = Vec::new();
+
+            for entry in glob(path_pattern).context("invalid include pattern")? {
+                paths.push(entry?);
+            }
+
+            paths
+
+        } else if path.exists() {
+            vec![path.to_path_buf()]
+
+        } else {
+            Vec::new()
+        };
+
+        if paths.is_empty() {
+            if optional {
+                debug!("no files found for optional include '{}', skip", path.display());
+                return Ok(paths);
+            }
+
+            return Err(anyhow!("included files weren't found for path '{}'", path.display()));
+        }
+
+        let mut files: Vec<PathBuf> = Vec::new();
+
+        for path in paths {
+            if path.is_dir() {
+                files.append(&mut get_files_from_dir(&path)?);
+
+            } else {
+                files.push(path);
+            }
+        }
+
+        debug!("files included by path '{}': {:?}", path.display(), files);
+
+        Ok(files)
+    }
+}
+
+fn get_files_from_dir(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("couldn't read directory '{}'", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+
+    entries.sort();
+
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for entry in entries {
+        if entry.is_dir() {
+            files.append(&mut get_files_from_dir(&entry)?);
+
+        } else {
+            files.push(entry);
+        }
+    }
+
+    Ok(files)
+}
+
+fn is_glob_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+#[cfg(test)]
+mod include_tests {
+    use std::path::Path;
+
+    use crate::apache::include::load_config_with_includes;
+    use crate::apache::context::ConfigContext;
+    use crate::apache::variables::Variables;
+
+    fn get_context() -> ConfigContext {
+        ConfigContext::new(Variables::default(), &[])
+    }
+
+    #[test]
+    fn included_files_should_be_inserted_in_place_of_include_directive() {
+        let root_config = Path::new("test-data/apache-root/apache2.conf");
+
+        let directives = load_config_with_includes(root_config, get_context()).unwrap();
+
+        assert!(directives.iter().all(|directive| !directive.is("Include")));
+        assert!(directives.iter().all(|directive| !directive.is("IncludeOptional")));
+
+        let names: Vec<&str> = directives.iter()
+            .map(|directive| directive.name.as_str()).collect();
+
+        assert_eq!(names, vec!["ServerName", "LoadModule", "Listen", "IfModule", "Listen", "Define",
+                               "Define", "VirtualHost", "Define", "VirtualHost", "VirtualHost",
+                               "LogLevel"]);
+    }
+
+    #[test]
+    fn relative_paths_should_be_resolved_from_server_root() {
+        let root_config = Path::new("test-data/apache-root/conf/httpd.conf");
+
+        let directives = load_config_with_includes(root_config, get_context()).unwrap();
+
+        assert!(directives.iter().any(|directive| directive.is("Listen")));
+        assert_eq!(directives.iter().filter(|directive| directive.is("VirtualHost")).count(), 2);
+    }
+
+    #[test]
+    fn variables_should_be_substituted_in_include_paths() {
+        let root_config = Path::new("test-data/apache-root/variables.conf");
+
+        let directives = load_config_with_includes(root_config, get_context()).unwrap();
+
+        let server_names: Vec<&str> = directives.iter()
+            .filter(|directive| directive.is("VirtualHost"))
+            .flat_map(|directive| directive.children())
+            .map(|directive| directive.args[0].as_str()).collect();
+
+        assert_eq!(server_names, vec!["www.example.com", "shop.example.com"]);
+    }
+
+    #[test]
+    fn return_error_for_missing_included_file() {
+        let root_config = Path::new("test-data/apache-root/missing-include.conf");
+        assert!(load_config_with_includes(root_config, get_context()).is_err());
+    }
+
+    #[test]
+    fn return_error_for_include_cycle() {
+        let root_config = Path::new("test-data/apache-root/cycle.conf");
+        assert!(load_config_with_includes(root_config, get_context()).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use crate::apache::parser::Directive;
+
+pub const MACRO_SECTION: &str = "Macro";
+pub const USE_DIRECTIVE: &str = "Use";
+pub const UNDEF_MACRO_DIRECTIVE: &str = "UndefMacro";
+
+/// mod_macro template:
+///
+/// ```apache
+/// <Macro VHost $name $port>
+///     <VirtualHost *:$port>
+///         ServerName $name
+///     </VirtualHost>
+/// </Macro>
+///
+/// Use VHost example.com 443
+/// ```
+#[derive(Clone, Debug)]
+struct Macro {
+    name: String,
+
+    /// Parameter names with prefix: `$name`, `%name`, `@name`
+    params: Vec<String>,
+
+    body: Vec<Directive>
+}
+
+/// Macros defined in config order.
+#[derive(Clone, Debug, Default)]
+pub struct Macros {
+    macros: Vec<Macro>
+}
+
+impl Macros {
+    /// `<Macro name params...>`
+    pub fn define(&mut self, section: &Directive) {
+        let mut args = section.args.iter();
+
+        let name = match args.next() {
+            Some(name) => name,
+            None => {
+                warn!("macro without name at line {}, skip", section.line);
+                return;
+            }
+        };
+
+        debug!("macro '{name}' defined at line {}", section.line);
+
+        self.undefine(name);
+
+        self.macros.push(Macro {
+            name: name.to_string(),
+            params: args.map(|param| param.to_string()).collect(),
+            body: section.children().to_vec()
+        });
+    }
+
+    /// Macro names are case-insensitive
+    pub fn undefine(&mut self, name: &str) {
+        self.macros.retain(|known_macro| !known_macro.name.eq_ignore_ascii_case(name));
+    }
+
+    /// Returns macro body for `Use name values...`, nested `Use` directives are expanded too.
+    ///
+    /// Unknown macros, wrong number of values and recursive macros are skipped with warning.
+    pub fn expand(&self, use_directive: &Directive) -> Vec<Directive> {
+        self.expand_use(use_directive, &mut Vec::new())
+    }
+
+    fn expand_use(&self, use_directive: &Directive, expanding: &mut Vec<String>) -> Vec<Directive> {
+        let (name, values) = match use_directive.args.split_first() {
+            Some((name, values)) => (name, values),
+            None => {
+                warn!("'Use' without macro name at line {}, skip", use_directive.line);
+                return Vec::new();
+            }
+        };
+
+        let known_macro = self.macros.iter()
+            .find(|known_macro| known_macro.name.eq_ignore_ascii_case(name));
+
+        let known_macro = match known_macro {
+            Some(known_macro) => known_macro,
+            None => {
+                warn!("macro '{name}' isn't defined, line {}, skip", use_directive.line);
+                return Vec::new();
+            }
+        };
+
+        if known_macro.params.len() != values.len() {
+            warn!("macro '{name}' expects {} values, got {} at line {}, skip",
+                  known_macro.params.len(), values.len(), use_directive.line);
+            return Vec::new();
+        }
+
+        if expanding.iter().any(|expanding_name| expanding_name.eq_ignore_ascii_case(name)) {
+            warn!("recursive use of macro '{name}' at line {}, skip", use_directive.line);
+            return Vec::new();
+        }
+
+        trace!("expand macro '{name}' with values {:?}", values);
+
+        // longer names first: `$name` mustn't replace part of `$name_alias`
+        let mut replacements: Vec<(&String, &String)> = known_macro.params.iter()
+                                                                  .zip(values).collect();
+        replacements.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+
+        expanding.push(name.to_string());
+
+        let body = known_macro.body.iter()
+            .map(|directive| substitute_params(directive, &replacements))
+            .collect();
+
+        let result = self.expand_nested(body, expanding);
+
+        expanding.pop();
+
+        result
+    }
+
+    fn expand_nested(&self, directives: Vec<Directive>, expanding: &mut Vec<String>) -> Vec<Directive> {
+        let mut results: Vec<Directive> = Vec::new();
+
+        for mut directive in directives {
+            if directive.is(USE_DIRECTIVE) && !directive.is_block() {
+                results.append(&mut self.expand_use(&directive, expanding));
+                continue;
+            }
+
+            if let Some(children) = directive.block.take() {
+                directive.block = Some(self.expand_nested(children, expanding));
+            }
+
+            results.push(directive);
+        }
+
+        results
+    }
+}
+
+fn substitute_params(directive: &Directive, replacements: &[(&String, &String)]) -> Directive {
+    let substitute = |value: &String| {
+        replacements.iter().fold(value.to_string(),
+                                 |result, (param, value)| result.replace(param.as_str(), value))
+    };
+
+    Directive {
+        name: substitute(&directive.name),
+        args: directive.args.iter().map(substitute).collect(),
+        line: directive.line,
+        block: directive.block.as_ref().map(
+            |children| children.iter().map(|child| substitute_params(child, replacements)).collect()
+        )
+    }
+}
+
+#[cfg(test)]
+mod macros_tests {
+    use crate::apache::macros::Macros;
+    use crate::apache::parser::parse_config;
+
+    fn get_macros(input: &str) -> Macros {
+        let mut macros = Macros::default();
+
+        for directive in parse_config(input).unwrap() {
+            macros.define(&directive);
+        }
+
+        macros
+    }
+
+    #[test]
+    fn macro_body_should_be_expanded_with_values() {
+        let macros = get_macros("<Macro VHost $name $name_alias $port>\n\
+                                 <VirtualHost *:$port>\n    ServerName $name\n    \
+                                 ServerAlias $name_alias\n</VirtualHost>\n</Macro>");
+
+        let use_directive = parse_config("Use vhost example.com www.example.com 443").unwrap();
+
+        let directives = macros.expand(&use_directive[0]);
+
+        assert_eq!(directives.len(), 1);
+
+        let vhost = &directives[0];
+        assert_eq!(vhost.args, vec!["*:443"]);
+        assert_eq!(vhost.children()[0].args, vec!["example.com"]);
+        assert_eq!(vhost.children()[1].args, vec!["www.example.com"]);
+    }
+
+    #[test]
+    fn nested_macros_should_be_expanded() {
+        let macros = get_macros("<Macro Site $name>\n    ServerName $name\n</Macro>\n\
+                                 <Macro SecureSite $name>\n    <VirtualHost *:443>\n        \
+                                 Use Site $name\n        SSLEngine on\n    </VirtualHost>\n</Macro>");
+
+        let use_directive = parse_config("Use SecureSite shop.example.com").unwrap();
+
+        let directives = macros.expand(&use_directive[0]);
+
+        let vhost = &directives[0];
+        assert_eq!(vhost.children()[0].name, "ServerName");
+        assert_eq!(vhost.children()[0].args, vec!["shop.example.com"]);
+        assert_eq!(vhost.children()[1].name, "SSLEngine");
+    }
+
+    #[test]
+    fn unsupported_use_should_be_skipped() {
+        let mut macros = get_macros("<Macro Site $name>\n    ServerName $name\n</Macro>\n\
+                                     <Macro Loop $name>\n    Use Loop $name\n</Macro>");
+
+        for input in ["Use Unknown example.com", "Use Site", "Use Loop example.com"] {
+            let use_directive = parse_config(input).unwrap();
+            assert!(macros.expand(&use_directive[0]).is_empty(), "input '{}'", input);
+        }
+
+        macros.undefine("site");
+
+        let use_directive = parse_config("Use Site example.com").unwrap();
+        assert!(macros.expand(&use_directive[0]).is_empty());
+    }
+}
@@ -0,0 +1,620 @@
+use std::collections::HashMap;
+use std::net::Ipv6Addr;
+use std::path::Path;
+
+use crate::apache::context::{ConfigContext, evaluate_config};
+use crate::apache::include::load_config_with_includes;
+use crate::apache::parser::{Directive, parse_config_file};
+use crate::apache::redirect::get_redirect;
+use crate::apache::status::get_expected_status;
+use crate::apache::variables::Variables;
+use crate::annotation::{ANNOTATION_DIRECTIVE, get_annotations};
+use crate::domain::{Certificate, NameResponse, VirtualHost};
+use crate::vhost::{VhostDiscoveryConfig, WebServer};
+use crate::webserver::{is_valid_domain_name, ListenAddress, normalize_domain_name, parse_listen_address,
+                       split_host_port};
+
+pub mod parser;
+
+pub mod include;
+
+pub mod variables;
+
+pub mod context;
+
+pub mod macros;
+
+pub mod redirect;
+
+pub mod status;
+
+const VIRTUAL_HOST_SECTION: &str = "VirtualHost";
+const SERVER_NAME_DIRECTIVE: &str = "ServerName";
+const SERVER_ALIAS_DIRECTIVE: &str = "ServerAlias";
+const SSL_ENGINE_DIRECTIVE: &str = "SSLEngine";
+const SSL_CERTIFICATE_FILE_DIRECTIVE: &str = "SSLCertificateFile";
+const LISTEN_DIRECTIVE: &str = "Listen";
+
+/// Debian/Ubuntu: environment variables for apache, located near `apache2.conf`
+const ENVVARS_FILE: &str = "envvars";
+
+pub fn get_apache_discovery_config(include_subdirs: bool, file_extensions: &[String],
+                                   modules: &[String]) -> VhostDiscoveryConfig {
+    VhostDiscoveryConfig {
+        web_server: WebServer::Apache,
+        include_subdirs,
+        file_extensions: file_extensions.to_vec(),
+        modules: modules.to_vec()
+    }
+}
+
+/// `modules` - loaded modules for `<IfModule>` evaluation, sections are active if empty
+pub fn get_virtual_hosts_from_file(vhost_file: &Path,
+                                   modules: &[String]) -> anyhow::Result<Vec<VirtualHost>> {
+    info!("get virtual hosts from file '{}'", vhost_file.display());
+
+    let mut context = ConfigContext::new(Variables::from_env(), modules);
+    let directives = evaluate_config(parse_config_file(vhost_file)?, &mut context);
+
+    Ok(get_virtual_hosts_from_directives(&directives))
+}
+
+/// Discover virtual hosts loaded by apache, starting from the main config file and
+/// following `Include` / `IncludeOptional` directives.
+///
+/// Variables are taken from process environment and `envvars` file near the main config.
+/// `<IfModule>` sections are evaluated with `LoadModule` directives and user supplied `modules`.
+pub fn get_virtual_hosts_from_root_config(root_config: &Path,
+                                          modules: &[String]) -> anyhow::Result<Vec<VirtualHost>> {
+    info!("get virtual hosts from root config '{}'", root_config.display());
+
+    let mut variables = Variables::from_env();
+
+    let envvars_file = root_config.parent().unwrap_or(Path::new("")).join(ENVVARS_FILE);
+
+    if envvars_file.is_file() {
+        variables.load_envvars_file(&envvars_file)?;
+    }
+
+    let directives = load_config_with_includes(root_config, ConfigContext::new(variables, modules))?;
+
+    Ok(filter_by_listens(get_virtual_hosts_from_directives(&directives), &get_listens(&directives)))
+}
+
+pub fn get_virtual_hosts_from_directives(directives: &[Directive]) -> Vec<VirtualHost> {
+    get_virtual_host_sections(directives).into_iter()
+        .flat_map(get_virtual_hosts_from_section)
+        .collect()
+}
+
+/// Vhosts are validated with `Listen` directives if config has them. Only the whole config
+/// has all `Listen` directives, a single vhost file may open its own ports only.
+fn filter_by_listens(vhosts: Vec<VirtualHost>, listens: &[ListenAddress]) -> Vec<VirtualHost> {
+    if listens.is_empty() {
+        return vhosts;
+    }
+
+    vhosts.into_iter()
+        .filter(|vhost| {
+            let opened = is_port_opened(vhost, listens);
+
+            if !opened {
+                debug!("port {} of vhost '{}' isn't opened by Listen directive, skip",
+                       vhost.port, vhost.domain);
+            }
+
+            opened
+        })
+        .collect()
+}
+
+/// `Listen` directives from all sections, e.g. `<IfModule ssl_module>`.
+///
+/// Supported formats: `Listen 80`, `Listen 10.0.0.1:80`, `Listen [::]:443 https`
+fn get_listens(directives: &[Directive]) -> Vec<ListenAddress> {
+    let mut listens: Vec<ListenAddress> = Vec::new();
+
+    for directive in directives {
+        if directive.is(LISTEN_DIRECTIVE) {
+            match directive.args.first().and_then(|value| parse_listen_address(value)) {
+                Some(listen) if listen.port.is_some() => listens.push(listen),
+                _ => debug!("unsupported Listen value at line {}", directive.line)
+            }
+
+        } else if directive.is_block() {
+            listens.append(&mut get_listens(directive.children()));
+        }
+    }
+
+    listens
+}
+
+/// Listen without address opens port on all addresses, vhost `*:port` matches any address.
+fn is_port_opened(vhost: &VirtualHost, listens: &[ListenAddress]) -> bool {
+    listens.iter().any(|listen| {
+        listen.port == Some(vhost.port) &&
+            (listen.address.is_none() || vhost.address.is_none() || listen.address == vhost.address)
+    })
+}
+
+fn get_virtual_host_sections(directives: &[Directive]) -> Vec<&Directive> {
+    let mut sections: Vec<&Directive> = Vec::new();
+
+    for directive in directives {
+        if directive.is(VIRTUAL_HOST_SECTION) {
+            sections.push(directive);
+
+        } else if directive.is_block() {
+            sections.append(&mut get_virtual_host_sections(directive.children()));
+        }
+    }
+
+    sections
+}
+
+/// Returns vhost for every address of the section: `<VirtualHost *:80 *:443>`
+fn get_virtual_hosts_from_section(section: &Directive) -> Vec<VirtualHost> {
+    trace!("virtual host section at line {}", section.line);
+
+    let annotations = get_annotations(section.children().iter()
+        .filter(|directive| directive.name == ANNOTATION_DIRECTIVE)
+        .map(|directive| directive.args.as_slice()));
+
+    if annotations.ignore {
+        debug!("virtual host at line {} is ignored by annotation", section.line);
+        return Vec::new();
+    }
+
+    let mut addresses: Vec<(Option<String>, i32)> = Vec::new();
+
+    for value in &section.args {
+        match parse_listen_address(value) {
+            Some(ListenAddress { address, port: Some(port) }) => {
+                if !addresses.contains(&(address.clone(), port)) {
+                    addresses.push((address, port));
+                }
+            }
+            _ => debug!("unsupported virtual host address '{value}' at line {}", section.line)
+        }
+    }
+
+    let server_name = section.children().iter()
+        .find(|directive| directive.is(SERVER_NAME_DIRECTIVE))
+        .and_then(get_server_name);
+
+    let tls = server_name.as_ref().is_some_and(|(_, https)| *https) ||
+        section.children().iter().any(|directive| {
+            directive.is(SSL_ENGINE_DIRECTIVE) &&
+                directive.args.first().is_some_and(|value| value.eq_ignore_ascii_case("on"))
+        });
+
+    let domain = server_name.map(|(name, _)| name);
+
+    let aliases: Vec<String> = section.children().iter()
+        .filter(|directive| directive.is(SERVER_ALIAS_DIRECTIVE))
+        .flat_map(get_server_aliases)
+        .collect();
+
+    let certificate = section.children().iter()
+        .find(|directive| directive.is(SSL_CERTIFICATE_FILE_DIRECTIVE))
+        .and_then(|directive| directive.args.first())
+        .map(|path| Certificate { path: path.to_string(), ..Default::default() });
+
+    match domain {
+        Some(domain) if !addresses.is_empty() => {
+            debug!("domain found {domain}, addresses {:?}, tls {tls}, aliases {:?}",
+                   addresses, aliases);
+
+            addresses.into_iter()
+                .map(|(address, port)| {
+                    let mut vhost = VirtualHost {
+                        domain: domain.to_string(), port, tls, address, aliases: aliases.clone(),
+                        redirect: None, default_server: false, backends: Vec::new(),
+                        certificate: certificate.clone(), paths: annotations.paths.clone(),
+                        display_name: annotations.name.clone(),
+                        expected_status: annotations.expected_status, tags: annotations.tags.clone(),
+                        alias_responses: HashMap::new()
+                    };
+
+                    vhost.alias_responses = aliases.iter()
+                        .filter(|alias| is_valid_domain_name(alias))
+                        .map(|alias| {
+                            let alias_vhost = VirtualHost { domain: alias.to_string(), ..vhost.clone() };
+                            (alias.to_string(), get_name_response(section, &alias_vhost))
+                        })
+                        .collect();
+
+                    let response = get_name_response(section, &vhost);
+                    vhost.redirect = response.redirect;
+                    vhost.expected_status = response.expected_status;
+
+                    if let Some(redirect) = &vhost.redirect {
+                        debug!("redirect {} to '{}' was detected for virtual host at line {}, port {}",
+                               redirect.status, redirect.target, section.line, vhost.port);
+                    }
+
+                    vhost
+                })
+                .collect()
+        }
+        _ => {
+            debug!("virtual host at line {} doesn't have domain or port, skip", section.line);
+            Vec::new()
+        }
+    }
+}
+
+/// Redirect for the vhost name, expected status is inferred if the name isn't redirected
+/// and annotation doesn't set it.
+fn get_name_response(section: &Directive, vhost: &VirtualHost) -> NameResponse {
+    let redirect = get_redirect(section, vhost);
+
+    let expected_status = match vhost.expected_status {
+        None if redirect.is_none() => get_expected_status(section),
+        expected_status => expected_status
+    };
+
+    NameResponse { redirect, expected_status }
+}
+
+/// Supported formats: `ServerName example.com`, `ServerName example.com:80`,
+/// `ServerName https://example.com:443`, `ServerName [2001:db8::1]:80`.
+///
+/// Returns name and `true` for `https` scheme.
+fn get_server_name(server_name: &Directive) -> Option<(String, bool)> {
+    if server_name.args.len() != 1 {
+        debug!("unsupported server name at line {}", server_name.line);
+        return None;
+    }
+
+    let value = &server_name.args[0];
+
+    let (https, host_port) = match value.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("https") => (true, rest),
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") => (false, rest),
+        Some(_) => (false, ""),
+        None => (false, value.as_str())
+    };
+
+    let name = match split_host_port(host_port) {
+        Some((host, _)) if host_port.starts_with('[') =>
+            host.parse::<Ipv6Addr>().ok().map(|ip| format!("[{ip}]")),
+        Some((host, _)) => normalize_domain_name(host).filter(|name| is_valid_domain_name(name)),
+        None => None
+    };
+
+    match name {
+        Some(name) => Some((name, https)),
+        _ => {
+            debug!("unsupported server name '{value}' at line {}", server_name.line);
+            None
+        }
+    }
+}
+
+/// Aliases may contain wildcards: `*.example.com`
+fn get_server_aliases(server_alias: &Directive) -> Vec<String> {
+    server_alias.args.iter()
+        .filter_map(|alias| {
+            let name = normalize_domain_name(alias)
+                .filter(|name| is_valid_domain_name(&name.replace(['*', '?'], "a")));
+
+            if name.is_none() {
+                debug!("unsupported server alias '{alias}' at line {}", server_alias.line);
+            }
+
+            name
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod apache_tests {
+    use std::path::Path;
+
+    use crate::{get_apache_discovery_config, get_vhosts};
+    use crate::apache::{get_server_name, get_virtual_hosts_from_directives, get_virtual_hosts_from_file,
+                        get_virtual_hosts_from_root_config};
+    use crate::apache::parser::parse_config;
+    use crate::filter::{expand_aliases, WildcardPolicy};
+    use crate::test_utils::assert_vhost_in_vec;
+
+    #[test]
+    fn get_virtual_hosts_from_apache_file() {
+        let vhosts_path = Path::new("test-data/apache-vhosts");
+
+        let config = get_apache_discovery_config(false, &[".conf".to_string()], &[]);
+
+        let vhosts = get_vhosts(vhosts_path, &config, false).unwrap();
+
+        for vhost in &vhosts {
+            println!("{}", vhost);
+        }
+
+        let expected_size: usize = 4;
+        assert_eq!(&vhosts.len(), &expected_size);
+
+        assert_vhost_in_vec(&vhosts, "collections.museum.ru", 8081);
+        assert_vhost_in_vec(&vhosts, "whatever.ru", 443);
+        assert_vhost_in_vec(&vhosts, "whatever.ru", 5380);
+        assert_vhost_in_vec(&vhosts, "demo.company.ru", 1480);
+    }
+
+    #[test]
+    fn server_aliases_should_be_collected() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/aliases.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 2);
+
+        let first_vhost = vhosts.first().unwrap();
+        assert_eq!(first_vhost.domain, "example.com");
+        assert_eq!(first_vhost.port, 443);
+        assert!(first_vhost.tls);
+        assert_eq!(first_vhost.aliases,
+                   vec!["www.example.com", "api.example.com", "static.example.com", "*.cdn.example.com"]);
+
+        let last_vhost = vhosts.last().unwrap();
+        assert_eq!(last_vhost.domain, "shop.example.com");
+        assert!(last_vhost.aliases.is_empty());
+        assert!(!last_vhost.tls);
+    }
+
+    #[test]
+    fn tls_should_be_detected_by_ssl_engine_directive() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/ssl-engine.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 2);
+
+        let tls_vhost = vhosts.first().unwrap();
+        assert_eq!(tls_vhost.port, 8443);
+        assert!(tls_vhost.tls);
+        assert_eq!(tls_vhost.certificate.as_ref().unwrap().path, "/etc/ssl/secure.example.com.pem");
+
+        assert!(!vhosts.last().unwrap().tls);
+        assert!(vhosts.last().unwrap().certificate.is_none());
+    }
+
+    #[test]
+    fn monitoring_paths_should_be_taken_from_annotations() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/paths.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts[0].paths, vec!["/healthz"]);
+    }
+
+    #[test]
+    fn annotations_should_be_applied_to_virtual_hosts() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/annotations.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 1);
+
+        assert_eq!(vhosts[0].domain, "billing.example.com");
+        assert_eq!(vhosts[0].display_name.as_deref(), Some("Billing"));
+        assert_eq!(vhosts[0].expected_status, Some(401));
+        assert_eq!(vhosts[0].tags, vec!["team:payments"]);
+    }
+
+    #[test]
+    fn expected_status_should_be_inferred_from_access_control() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/expected-status.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        let statuses: Vec<(&str, Option<u16>)> = vhosts.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.expected_status)).collect();
+
+        assert_eq!(statuses, vec![
+            ("internal.example.com", Some(403)),
+            ("admin.example.com", Some(401)),
+            ("www.example.com", None),
+        ]);
+    }
+
+    #[test]
+    fn ipv6_virtual_host_addresses_should_be_supported() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/ipv6.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        let listens: Vec<(&str, Option<&str>, i32)> = vhosts.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.address.as_deref(), vhost.port)).collect();
+
+        assert_eq!(listens, vec![
+            ("ipv6.example.com", Some("::"), 443),
+            ("ipv6.example.com", Some("2001:db8::1"), 8080),
+            ("default.example.com", None, 8081),
+        ]);
+    }
+
+    #[test]
+    fn vhost_should_be_collected_for_every_address() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/multiple-addresses.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        let listens: Vec<(&str, Option<&str>, i32)> = vhosts.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.address.as_deref(), vhost.port)).collect();
+
+        assert_eq!(listens, vec![
+            ("both.example.com", None, 80),
+            ("both.example.com", None, 443),
+            ("multi-ip.example.com", Some("10.0.0.1"), 80),
+            ("multi-ip.example.com", Some("10.0.0.2"), 8080),
+        ]);
+    }
+
+    #[test]
+    fn vhosts_without_listen_directive_should_be_skipped() {
+        let root_config = Path::new("test-data/apache-root/listen.conf");
+
+        let vhosts = get_virtual_hosts_from_root_config(root_config, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        let listens: Vec<(Option<&str>, i32)> = vhosts.iter()
+            .map(|vhost| (vhost.address.as_deref(), vhost.port)).collect();
+
+        assert_eq!(listens, vec![(None, 80), (None, 443), (Some("10.0.0.1"), 8080)]);
+    }
+
+    #[test]
+    fn server_name_with_scheme_and_port_should_be_supported() {
+        let cases = [
+            ("ServerName example.com", Some(("example.com", false))),
+            ("ServerName Example.com:8080", Some(("example.com", false))),
+            ("ServerName https://www.example.com:443", Some(("www.example.com", true))),
+            ("ServerName http://example.com", Some(("example.com", false))),
+            ("ServerName https://пример.рф", Some(("пример.рф", true))),
+            ("ServerName [2001:db8::1]:80", Some(("[2001:db8::1]", false))),
+            ("ServerName https://[::1]", Some(("[::1]", true))),
+            ("ServerName ftp://example.com", None),
+            ("ServerName example.com:http", None),
+            ("ServerName [example.com]:80", None),
+        ];
+
+        for (input, expected) in cases {
+            let directive = &parse_config(input).unwrap()[0];
+
+            let server_name = get_server_name(directive);
+
+            assert_eq!(server_name.as_ref().map(|(name, https)| (name.as_str(), *https)), expected,
+                       "input '{}'", input);
+        }
+    }
+
+    #[test]
+    fn https_scheme_of_server_name_should_enable_tls() {
+        let vhosts = get_virtual_hosts_from_directives(&parse_config(
+            "<VirtualHost *:443>\n    ServerName https://www.example.com:443\n</VirtualHost>").unwrap());
+
+        assert_eq!(vhosts.len(), 1);
+        assert_eq!(vhosts[0].domain, "www.example.com");
+        assert!(vhosts[0].tls);
+    }
+
+    #[test]
+    fn redirect_should_be_evaluated_for_every_alias() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/alias-redirect.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        let vhosts = expand_aliases(&vhosts, &WildcardPolicy::Skip);
+        println!("{:?}", vhosts);
+
+        let redirects: Vec<(&str, Option<&str>)> = vhosts.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.redirect.as_ref().map(|redirect| redirect.target.as_str())))
+            .collect();
+
+        assert_eq!(redirects, vec![("example.com", None), ("www.example.com", Some("https://example.com/"))]);
+    }
+
+    #[test]
+    fn vhost_file_listen_directives_should_not_skip_ports_opened_in_other_files() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/split-listen.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        let listens: Vec<(&str, i32)> = vhosts.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.port)).collect();
+
+        assert_eq!(listens, vec![("a.example.com", 80), ("b.example.com", 8443)]);
+    }
+
+    #[test]
+    fn vhosts_should_be_collected_from_included_files() {
+        let root_config = Path::new("test-data/apache-root/apache2.conf");
+
+        let vhosts = get_virtual_hosts_from_root_config(root_config, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 3);
+
+        assert_vhost_in_vec(&vhosts, "www.example.com", 80);
+        assert_vhost_in_vec(&vhosts, "shop.example.com", 443);
+        assert_vhost_in_vec(&vhosts, "envvars.example.com", 8081);
+    }
+
+    #[test]
+    fn vhosts_inside_if_module_should_be_collected_for_supplied_modules() {
+        let root_config = Path::new("test-data/apache-root/apache2.conf");
+
+        let vhosts = get_virtual_hosts_from_root_config(
+            root_config, &["php7_module".to_string()]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 4);
+
+        assert_vhost_in_vec(&vhosts, "php.example.com", 80);
+        assert!(vhosts.iter().all(|vhost| vhost.domain != "dev.example.com"));
+    }
+
+    #[test]
+    fn vhost_should_be_collected_for_every_macro_use() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/macros.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 3);
+
+        assert_vhost_in_vec(&vhosts, "example.com", 443);
+        assert_vhost_in_vec(&vhosts, "shop.example.com", 8443);
+        assert_vhost_in_vec(&vhosts, "blog.example.com", 80);
+
+        let tls_vhost = vhosts.first().unwrap();
+        assert!(tls_vhost.tls);
+        assert_eq!(tls_vhost.aliases, vec!["www.example.com"]);
+    }
+
+    #[test]
+    fn redirect_vhosts_should_have_redirect() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/redirects.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 4);
+
+        let redirects: Vec<(&str, u16, &str)> = vhosts.iter()
+            .filter_map(|vhost| vhost.redirect.as_ref().map(
+                |redirect| (vhost.domain.as_str(), redirect.status, redirect.target.as_str())))
+            .collect();
+
+        assert_eq!(redirects, vec![("example.com", 301, "https://example.com/"),
+                                   ("old.example.com", 301, "https://example.com/")]);
+
+        let sites: Vec<&str> = vhosts.iter()
+            .filter(|vhost| vhost.redirect.is_none())
+            .map(|vhost| vhost.domain.as_str()).collect();
+
+        assert_eq!(sites, vec!["example.com", "shop.example.com"]);
+    }
+
+    #[test]
+    fn international_and_uppercase_names_should_be_normalized() {
+        let vhost_file_path = Path::new("test-data/apache-aliases/idn.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path, &[]).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 2);
+
+        assert_eq!(vhosts[0].domain, "пример.рф");
+        assert_eq!(vhosts[0].aliases, vec!["www.пример.рф", "*.пример.рф"]);
+
+        assert_eq!(vhosts[1].domain, "shop.example.com");
+    }
+}
@@ -0,0 +1,317 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+
+use crate::annotation::{ANNOTATION_DIRECTIVE, parse_annotation};
+
+/// Apache config directive.
+///
+/// Simple directive:
+///
+/// ```apache
+/// ServerName example.com
+/// ```
+///
+/// Section (`VirtualHost`, `IfModule`, `Directory`, etc.):
+///
+/// ```apache
+/// <VirtualHost *:80>
+///     ServerName example.com
+/// </VirtualHost>
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Directive {
+    pub name: String,
+
+    pub args: Vec<String>,
+
+    /// Line number where directive starts (starts with 1)
+    pub line: usize,
+
+    /// Nested directives, `None` for simple directives
+    pub block: Option<Vec<Directive>>
+}
+
+impl Directive {
+    /// Directive names are case-insensitive
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    pub fn is_block(&self) -> bool {
+        self.block.is_some()
+    }
+
+    pub fn children(&self) -> &[Directive] {
+        match &self.block {
+            Some(children) => children,
+            None => &[]
+        }
+    }
+}
+
+pub fn parse_config_file(config_file: &Path) -> anyhow::Result<Vec<Directive>> {
+    let input = fs::read_to_string(config_file)
+        .with_context(|| format!("couldn't read config file '{}'", config_file.display()))?;
+
+    parse_config(&input)
+        .with_context(|| format!("couldn't parse config file '{}'", config_file.display()))
+}
+
+pub fn parse_config(input: &str) -> anyhow::Result<Vec<Directive>> {
+    let mut root: Vec<Directive> = Vec::new();
+    let mut open_sections: Vec<Directive> = Vec::new();
+
+    for (line, row) in get_logical_lines(input) {
+        trace!("row '{row}'");
+
+        let row = row.trim();
+
+        if let Some(comment) = row.strip_prefix('#') {
+            if let Some(args) = parse_annotation(comment) {
+                let directive = Directive { name: ANNOTATION_DIRECTIVE.to_string(), args, line, block: None };
+
+                match open_sections.last_mut() {
+                    Some(parent) => parent.block.get_or_insert_with(Vec::new).push(directive),
+                    None => root.push(directive)
+                }
+            }
+            continue;
+        }
+
+        if row.is_empty() {
+            continue;
+        }
+
+        if let Some(section_name) = row.strip_prefix("</") {
+            let section_name = section_name.trim_end_matches('>').trim();
+
+            let section = open_sections.pop().ok_or_else(
+                || anyhow!("unexpected '</{section_name}>' at line {line}"))?;
+
+            if !section.is(section_name) {
+                return Err(anyhow!("unexpected '</{}>' at line {}, section '{}' \
+                                    opened at line {} isn't closed",
+                                   section_name, line, section.name, section.line));
+            }
+
+            match open_sections.last_mut() {
+                Some(parent) => parent.block.get_or_insert_with(Vec::new).push(section),
+                None => root.push(section)
+            }
+
+        } else if let Some(section_row) = get_section_start(row, line) {
+            let mut words = split_words(section_row.trim_start_matches('<').trim_end_matches('>'));
+
+            if words.is_empty() {
+                return Err(anyhow!("section without name at line {line}"));
+            }
+
+            let name = words.remove(0);
+
+            open_sections.push(Directive { name, args: words, line, block: Some(Vec::new()) });
+
+        } else {
+            let mut words = split_words(row);
+            let name = words.remove(0);
+
+            let directive = Directive { name, args: words, line, block: None };
+
+            match open_sections.last_mut() {
+                Some(parent) => parent.block.get_or_insert_with(Vec::new).push(directive),
+                None => root.push(directive)
+            }
+        }
+    }
+
+    match open_sections.last() {
+        Some(section) => Err(anyhow!("unexpected end of file, section '{}' opened at line {} \
+                                      isn't closed", section.name, section.line)),
+        None => Ok(root)
+    }
+}
+
+/// Joins rows ending with `\` and returns them with the number of the first row.
+fn get_logical_lines(input: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+
+    let mut current: Option<(usize, String)> = None;
+
+    for (index, row) in input.lines().enumerate() {
+        let (line, mut value) = current.take().unwrap_or((index + 1, String::new()));
+
+        match row.strip_suffix('\\') {
+            Some(continued_row) => {
+                value.push_str(continued_row);
+                value.push(' ');
+                current = Some((line, value));
+            }
+            None => {
+                value.push_str(row);
+                lines.push((line, value));
+            }
+        }
+    }
+
+    if let Some(last_line) = current {
+        lines.push(last_line);
+    }
+
+    lines
+}
+
+/// Section start row: `<VirtualHost *:80>`.
+///
+/// Junk right before the section (`junk<VirtualHost *:80>`) is ignored with warning.
+fn get_section_start(row: &str, line: usize) -> Option<&str> {
+    if !row.ends_with('>') {
+        return None;
+    }
+
+    if row.starts_with('<') {
+        return Some(row);
+    }
+
+    let first_word = row.split_whitespace().next()?;
+
+    match first_word.find('<') {
+        Some(position) => {
+            warn!("unexpected text '{}' before section at line {line}, ignore",
+                  &first_word[..position]);
+            Some(&row[position..])
+        }
+        None => None
+    }
+}
+
+/// Splits by whitespaces, quoted values are single words.
+fn split_words(row: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+
+    let mut chars = row.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut word = String::new();
+
+        if c == '"' || c == '\'' {
+            chars.next();
+
+            while let Some(quoted_char) = chars.next() {
+                match quoted_char {
+                    '\\' if chars.peek() == Some(&c) => word.push(chars.next().unwrap()),
+                    _ if quoted_char == c => break,
+                    _ => word.push(quoted_char)
+                }
+            }
+
+        } else {
+            while let Some(&word_char) = chars.peek() {
+                if word_char.is_whitespace() {
+                    break;
+                }
+                word.push(word_char);
+                chars.next();
+            }
+        }
+
+        words.push(word);
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use crate::apache::parser::parse_config;
+
+    #[test]
+    fn sections_should_be_parsed_with_line_numbers() {
+        let input = "<IfModule mod_ssl.c>\n    <VirtualHost *:443>\n        ServerName example.com\n\n        \
+                     <Location /api>\n            Require all granted\n        </Location>\n    \
+                     </VirtualHost>\n</IfModule>\n";
+
+        let directives = parse_config(input).unwrap();
+
+        assert_eq!(directives.len(), 1);
+
+        let if_module = directives.first().unwrap();
+        assert!(if_module.is("ifmodule"));
+        assert_eq!(if_module.args, vec!["mod_ssl.c"]);
+
+        let vhost = if_module.children().first().unwrap();
+        assert_eq!(vhost.name, "VirtualHost");
+        assert_eq!(vhost.args, vec!["*:443"]);
+        assert_eq!(vhost.line, 2);
+        assert_eq!(vhost.children().len(), 2);
+
+        let server_name = vhost.children().first().unwrap();
+        assert_eq!(server_name.args, vec!["example.com"]);
+        assert_eq!(server_name.line, 3);
+
+        let location = vhost.children().last().unwrap();
+        assert_eq!(location.line, 5);
+        assert_eq!(location.children().first().unwrap().args, vec!["all", "granted"]);
+    }
+
+    #[test]
+    fn continued_lines_should_be_joined() {
+        let directives = parse_config("ServerAlias a.example.com \\\n    b.example.com\nListen 80").unwrap();
+
+        assert_eq!(directives.len(), 2);
+
+        let server_alias = directives.first().unwrap();
+        assert_eq!(server_alias.args, vec!["a.example.com", "b.example.com"]);
+        assert_eq!(server_alias.line, 1);
+
+        assert_eq!(directives.last().unwrap().line, 3);
+    }
+
+    #[test]
+    fn quoted_values_should_be_single_args() {
+        let directives = parse_config(r#"Header set X-Test "a b \"c\"""#).unwrap();
+
+        assert_eq!(directives.first().unwrap().args, vec!["set", "X-Test", r#"a b "c""#]);
+    }
+
+    #[test]
+    fn comments_should_be_ignored() {
+        let directives = parse_config("# <VirtualHost *:80>\n  #ServerName example.com\nListen 80").unwrap();
+
+        assert_eq!(directives.len(), 1);
+    }
+
+    #[test]
+    fn annotation_comments_should_be_kept_as_directives() {
+        let directives = parse_config("<VirtualHost *:80>\n    # vhdt: path=/healthz\n    \
+                                       ServerName example.com\n</VirtualHost>").unwrap();
+
+        let annotation = &directives[0].children()[0];
+        assert_eq!(annotation.name, "#vhdt");
+        assert_eq!(annotation.args, vec!["path=/healthz"]);
+        assert_eq!(annotation.line, 2);
+    }
+
+    #[test]
+    fn junk_before_section_should_be_ignored() {
+        let directives = parse_config("junk<VirtualHost *:80>\n</VirtualHost>").unwrap();
+
+        assert_eq!(directives.len(), 1);
+        assert!(directives.first().unwrap().is("VirtualHost"));
+    }
+
+    #[test]
+    fn return_error_for_unclosed_section() {
+        assert!(parse_config("<VirtualHost *:80>\n    ServerName example.com\n").is_err());
+    }
+
+    #[test]
+    fn return_error_for_mismatched_section_end() {
+        assert!(parse_config("<VirtualHost *:80>\n</Directory>").is_err());
+    }
+}
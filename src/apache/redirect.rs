@@ -0,0 +1,436 @@
+use regex::Regex;
+
+use crate::apache::parser::Directive;
+use crate::domain::{Redirect, VirtualHost};
+use crate::site::get_url;
+
+const REDIRECT_DIRECTIVE: &str = "Redirect";
+const REDIRECT_PERMANENT_DIRECTIVE: &str = "RedirectPermanent";
+const REDIRECT_TEMP_DIRECTIVE: &str = "RedirectTemp";
+const REDIRECT_MATCH_DIRECTIVE: &str = "RedirectMatch";
+const REWRITE_ENGINE_DIRECTIVE: &str = "RewriteEngine";
+const REWRITE_COND_DIRECTIVE: &str = "RewriteCond";
+const REWRITE_RULE_DIRECTIVE: &str = "RewriteRule";
+
+/// Sections inside `<VirtualHost>` which don't change directive context
+const CONDITIONAL_SECTIONS: [&str; 3] = ["IfModule", "IfDefine", "IfVersion"];
+
+/// Paths for checking that pattern matches the whole site
+const SAMPLE_PATHS: [&str; 3] = ["/", "/index.html", "/some/path/page"];
+
+/// Returns redirect if every request to the vhost is redirected to another site:
+///
+/// - `Redirect / https://example.com`, `Redirect permanent / https://example.com`,
+///   `RedirectPermanent`, `RedirectTemp`
+/// - `RedirectMatch ^/(.*)$ https://example.com/$1`
+/// - `RewriteRule ^ https://%{HTTP_HOST}%{REQUEST_URI} [R=301,L]` with `RewriteEngine on`,
+///   `RewriteCond` guards are evaluated for the vhost, unknown conditions mean no redirect.
+///   Rules with relative target (`RewriteRule ^/?$ /new/ [R=301,L]`) are checked for request to `/`
+pub fn get_redirect(section: &Directive, vhost: &VirtualHost) -> Option<Redirect> {
+    let directives = get_vhost_directives(section.children());
+
+    let mut rewrite_engine = false;
+    let mut conditions: Vec<&Directive> = Vec::new();
+
+    for directive in directives {
+        if let Some(redirect) = get_site_redirect(directive, vhost) {
+            return Some(redirect);
+        }
+
+        if directive.is(REWRITE_ENGINE_DIRECTIVE) {
+            rewrite_engine = directive.args.first()
+                                      .is_some_and(|value| value.eq_ignore_ascii_case("on"));
+
+        } else if directive.is(REWRITE_COND_DIRECTIVE) {
+            conditions.push(directive);
+
+        } else if directive.is(REWRITE_RULE_DIRECTIVE) {
+            if rewrite_engine && evaluate_conditions(&conditions, vhost) == Some(true) {
+                if let Some(redirect) = get_rewrite_redirect(directive, vhost) {
+                    return Some(redirect);
+                }
+            }
+
+            conditions.clear();
+        }
+    }
+
+    None
+}
+
+/// Vhost directives including directives from conditional sections: `<IfModule mod_rewrite.c>`
+pub fn get_vhost_directives(directives: &[Directive]) -> Vec<&Directive> {
+    let mut results: Vec<&Directive> = Vec::new();
+
+    for directive in directives {
+        if CONDITIONAL_SECTIONS.iter().any(|section| directive.is(section)) {
+            results.append(&mut get_vhost_directives(directive.children()));
+
+        } else {
+            results.push(directive);
+        }
+    }
+
+    results
+}
+
+fn get_site_redirect(directive: &Directive, vhost: &VirtualHost) -> Option<Redirect> {
+    let args: Vec<&str> = directive.args.iter().map(|arg| arg.as_str()).collect();
+
+    let (status, path, target) = if directive.is(REDIRECT_DIRECTIVE) {
+        match args.as_slice() {
+            [path, target] => (None, *path, *target),
+            [status, path, target] => (Some(*status), *path, *target),
+            _ => return None
+        }
+
+    } else if directive.is(REDIRECT_PERMANENT_DIRECTIVE) || directive.is(REDIRECT_TEMP_DIRECTIVE) {
+        let status = if directive.is(REDIRECT_PERMANENT_DIRECTIVE) { "permanent" } else { "temp" };
+
+        match args.as_slice() {
+            [path, target] => (Some(status), *path, *target),
+            _ => return None
+        }
+
+    } else if directive.is(REDIRECT_MATCH_DIRECTIVE) {
+        let (status, pattern, target) = match args.as_slice() {
+            [pattern, target] => (None, *pattern, *target),
+            [status, pattern, target] => (Some(*status), *pattern, *target),
+            _ => return None
+        };
+
+        let regex = get_site_regex(pattern, false)?;
+
+        return create_redirect(status, target, &regex, vhost);
+
+    } else {
+        return None;
+    };
+
+    if path != "/" {
+        return None;
+    }
+
+    create_redirect(status, target, &Regex::new("^/").ok()?, vhost)
+}
+
+/// `RewriteRule pattern target [flags]` with `R` flag. Relative target (`/new/`) is resolved with vhost url.
+fn get_rewrite_redirect(rule: &Directive, vhost: &VirtualHost) -> Option<Redirect> {
+    let (pattern, target) = match (rule.args.first(), rule.args.get(1)) {
+        (Some(pattern), Some(target)) => (pattern, target),
+        _ => return None
+    };
+
+    let flags = get_flags(rule.args.get(2));
+
+    let redirect_flag = flags.iter().find_map(|flag| {
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (flag.as_str(), None)
+        };
+
+        if name.eq_ignore_ascii_case("R") || name.eq_ignore_ascii_case("redirect") {
+            Some(value)
+        } else {
+            None
+        }
+    })?;
+
+    let case_insensitive = flags.iter().any(|flag| flag.eq_ignore_ascii_case("NC"));
+
+    if target.starts_with('/') {
+        // relative redirect is sent to the same host, it's checked for request to `/`
+        // like nginx `return 301 /new/`
+        let regex = get_regex(pattern, case_insensitive)
+            .filter(|regex| !pattern.starts_with('!') && regex.is_match("/"))?;
+        let url = format!("{}{target}", get_url(&vhost.domain, vhost.port, vhost.tls));
+
+        return create_redirect(redirect_flag, &url, &regex, vhost)
+            .map(|redirect| Redirect { keeps_host: true, ..redirect });
+    }
+
+    let regex = get_site_regex(pattern, case_insensitive)?;
+
+    create_redirect(redirect_flag, target, &regex, vhost)
+}
+
+/// Target is resolved for request to `/`: `$1` from the pattern, `%{HTTP_HOST}`, `%{REQUEST_URI}`.
+fn create_redirect(status: Option<&str>, target: &str, regex: &Regex,
+                   vhost: &VirtualHost) -> Option<Redirect> {
+    let status = get_redirect_status(status)?;
+
+    if !is_absolute_url(target) {
+        return None;
+    }
+
+    let mut resolved_target = target.replace("%{HTTP_HOST}", &vhost.domain)
+                                    .replace("%{SERVER_NAME}", &vhost.domain)
+                                    .replace("%{REQUEST_URI}", "/");
+
+    if let Some(captures) = regex.captures("/") {
+        for index in (1..captures.len()).rev() {
+            let value = captures.get(index).map_or("", |capture| capture.as_str());
+            resolved_target = resolved_target.replace(&format!("${index}"), value);
+        }
+    }
+
+    Some(Redirect {
+        status,
+        target: resolved_target,
+        keeps_host: target.contains("%{HTTP_HOST}") || target.contains("%{SERVER_NAME}")
+    })
+}
+
+/// Conditions are joined with AND, `[OR]` flag joins condition with the next one.
+///
+/// Returns `None` if result depends on request (path, headers, files).
+fn evaluate_conditions(conditions: &[&Directive], vhost: &VirtualHost) -> Option<bool> {
+    let mut result = Some(true);
+    let mut or_group: Vec<Option<bool>> = Vec::new();
+
+    for condition in conditions {
+        let flags = get_flags(condition.args.get(2));
+
+        or_group.push(evaluate_condition(condition, vhost, &flags));
+
+        if !flags.iter().any(|flag| flag.eq_ignore_ascii_case("OR")) {
+            result = and(result, or(&or_group));
+            or_group.clear();
+        }
+    }
+
+    if !or_group.is_empty() {
+        result = and(result, or(&or_group));
+    }
+
+    result
+}
+
+/// Supported test strings: `%{HTTPS}`, `%{SERVER_NAME}`, `%{HTTP_HOST}`, `%{SERVER_PORT}`.
+///
+/// Supported patterns: regular expressions and `=value`, both with `!` prefix.
+fn evaluate_condition(condition: &Directive, vhost: &VirtualHost, flags: &[String]) -> Option<bool> {
+    let (test_string, pattern) = match (condition.args.first(), condition.args.get(1)) {
+        (Some(test_string), Some(pattern)) => (test_string.as_str(), pattern.as_str()),
+        _ => return None
+    };
+
+    let value = match test_string {
+        "%{HTTPS}" => if vhost.tls { "on".to_string() } else { "off".to_string() },
+        "%{SERVER_NAME}" | "%{HTTP_HOST}" => vhost.domain.to_string(),
+        "%{SERVER_PORT}" => vhost.port.to_string(),
+        _ => {
+            trace!("unsupported rewrite condition '{test_string}' at line {}", condition.line);
+            return None;
+        }
+    };
+
+    let (negative, pattern) = match pattern.strip_prefix('!') {
+        Some(pattern) => (true, pattern),
+        None => (false, pattern)
+    };
+
+    let case_insensitive = flags.iter().any(|flag| flag.eq_ignore_ascii_case("NC"));
+
+    let matched = if let Some(expected) = pattern.strip_prefix('=') {
+        if case_insensitive { value.eq_ignore_ascii_case(expected) } else { value == expected }
+
+    } else if pattern.starts_with(['-', '<', '>']) {
+        return None;
+
+    } else {
+        get_regex(pattern, case_insensitive)?.is_match(&value)
+    };
+
+    Some(matched != negative)
+}
+
+/// Returns regex if pattern matches all paths of the site
+fn get_site_regex(pattern: &str, case_insensitive: bool) -> Option<Regex> {
+    if pattern.starts_with('!') {
+        return None;
+    }
+
+    let regex = get_regex(pattern, case_insensitive)?;
+
+    if SAMPLE_PATHS.iter().all(|path| regex.is_match(path)) {
+        Some(regex)
+    } else {
+        None
+    }
+}
+
+fn get_regex(pattern: &str, case_insensitive: bool) -> Option<Regex> {
+    let pattern = if case_insensitive { format!("(?i){pattern}") } else { pattern.to_string() };
+
+    match Regex::new(&pattern) {
+        Ok(regex) => Some(regex),
+        Err(e) => {
+            debug!("unsupported regular expression '{pattern}': {}", e);
+            None
+        }
+    }
+}
+
+/// `[R=301,L]` -> `["R=301", "L"]`
+fn get_flags(value: Option<&String>) -> Vec<String> {
+    match value {
+        Some(value) => value.trim_start_matches('[').trim_end_matches(']')
+                            .split(',').map(|flag| flag.trim().to_string()).collect(),
+        None => Vec::new()
+    }
+}
+
+/// `permanent`, `temp`, `seeother` or 3xx status code, 302 if status isn't set
+fn get_redirect_status(value: Option<&str>) -> Option<u16> {
+    match value.map(|value| value.to_lowercase()).as_deref() {
+        None | Some("temp") => Some(302),
+        Some("permanent") => Some(301),
+        Some("seeother") => Some(303),
+        Some(status) => status.parse::<u16>().ok().filter(|code| (300..400).contains(code))
+    }
+}
+
+fn is_absolute_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+fn or(values: &[Option<bool>]) -> Option<bool> {
+    if values.contains(&Some(true)) {
+        Some(true)
+
+    } else if values.iter().all(|value| *value == Some(false)) {
+        Some(false)
+
+    } else {
+        None
+    }
+}
+
+fn and(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod redirect_tests {
+    use crate::apache::parser::parse_config;
+    use crate::apache::redirect::get_redirect;
+    use crate::domain::{Redirect, VirtualHost};
+
+    fn get_vhost_redirect(vhost_body: &str, tls: bool) -> Option<Redirect> {
+        let input = format!("<VirtualHost *:80>\n{vhost_body}\n</VirtualHost>");
+        let directives = parse_config(&input).unwrap();
+
+        let vhost = VirtualHost {
+            domain: "example.com".to_string(), port: if tls { 443 } else { 80 }, tls,
+            ..Default::default()
+        };
+
+        get_redirect(&directives[0], &vhost)
+    }
+
+    fn is_redirect(vhost_body: &str, tls: bool) -> bool {
+        get_vhost_redirect(vhost_body, tls).is_some()
+    }
+
+    #[test]
+    fn redirect_directives_should_be_detected() {
+        let redirects = [
+            "Redirect / https://example.com/",
+            "Redirect permanent / https://example.com/",
+            "Redirect 301 / https://example.com/",
+            "RedirectPermanent / https://example.com/",
+            "RedirectTemp / http://example.org/",
+            "RedirectMatch ^/(.*)$ https://example.com/$1",
+            "RedirectMatch 301 ^ https://example.com/",
+        ];
+
+        for redirect in redirects {
+            assert!(is_redirect(redirect, false), "'{}'", redirect);
+        }
+    }
+
+    #[test]
+    fn partial_redirects_should_be_ignored() {
+        let redirects = [
+            "Redirect /old https://example.com/new",
+            "Redirect gone /",
+            "Redirect 410 / https://example.com/",
+            "RedirectMatch ^/blog/(.*)$ https://blog.example.com/$1",
+            "RedirectMatch ^/(.*)$ /index.html",
+            "<Location /old>\n    Redirect / https://example.com/\n</Location>",
+        ];
+
+        for redirect in redirects {
+            assert!(!is_redirect(redirect, false), "'{}'", redirect);
+        }
+    }
+
+    #[test]
+    fn rewrite_rule_redirects_should_be_detected() {
+        let redirects = [
+            "RewriteEngine On\nRewriteRule ^ https://%{HTTP_HOST}%{REQUEST_URI} [R=301,L]",
+            "RewriteEngine on\nRewriteRule ^(.*)$ https://example.com$1 [L,R]",
+            "<IfModule mod_rewrite.c>\n    RewriteEngine On\n    \
+             RewriteRule (.*) https://example.com/$1 [redirect=permanent]\n</IfModule>",
+            "RewriteEngine On\nRewriteCond %{HTTPS} off\nRewriteRule ^ https://example.com [R=301]",
+            "RewriteEngine on\nRewriteCond %{SERVER_NAME} =www.example.com [OR]\n\
+             RewriteCond %{SERVER_NAME} =example.com\n\
+             RewriteRule ^ https://%{SERVER_NAME}%{REQUEST_URI} [END,NE,R=permanent]",
+            "RewriteEngine On\nRewriteCond %{HTTP_HOST} ^EXAMPLE\\.com$ [NC]\n\
+             RewriteRule ^ https://www.example.com%{REQUEST_URI} [R=301,L]",
+        ];
+
+        for redirect in redirects {
+            assert!(is_redirect(redirect, false), "'{}'", redirect);
+        }
+    }
+
+    #[test]
+    fn conditional_and_partial_rewrites_should_be_ignored() {
+        let rewrites = [
+            "RewriteRule ^ https://example.com [R=301,L]",
+            "RewriteEngine On\nRewriteRule ^ index.php [L]",
+            "RewriteEngine On\nRewriteRule ^/api/(.*)$ https://api.example.com/$1 [R=301,L]",
+            "RewriteEngine On\nRewriteRule ^ https://example.com [R=404]",
+            "RewriteEngine On\nRewriteCond %{HTTP_HOST} !^example\\.com$\n\
+             RewriteRule ^ https://example.com [R=301]",
+            "RewriteEngine On\nRewriteCond %{REQUEST_FILENAME} !-f\n\
+             RewriteRule ^ https://example.com [R=301]",
+        ];
+
+        for rewrite in rewrites {
+            assert!(!is_redirect(rewrite, false), "'{}'", rewrite);
+        }
+
+        assert!(!is_redirect("RewriteEngine On\nRewriteCond %{HTTPS} off\n\
+                              RewriteRule ^ https://example.com [R=301]", true));
+    }
+
+    #[test]
+    fn redirect_status_and_target_should_be_resolved() {
+        let cases = [
+            ("Redirect / https://example.org/", 302, "https://example.org/", false),
+            ("Redirect permanent / https://example.org", 301, "https://example.org", false),
+            ("Redirect seeother / https://example.org", 303, "https://example.org", false),
+            ("RedirectMatch 308 ^/(.*)$ https://example.org/$1", 308, "https://example.org/", false),
+            ("RewriteEngine On\nRewriteRule ^(.*)$ https://%{HTTP_HOST}$1 [R=301,L]",
+             301, "https://example.com/", true),
+            ("RewriteEngine On\nRewriteRule ^ https://%{SERVER_NAME}%{REQUEST_URI} [R]",
+             302, "https://example.com/", true),
+            ("RewriteEngine On\nRewriteRule ^/?$ /new/ [R=301,L]", 301, "http://example.com/new/", true),
+            ("RewriteEngine On\nRewriteRule ^/(.*)$ /site/$1 [R]", 302, "http://example.com/site/", true),
+        ];
+
+        for (vhost_body, status, target, keeps_host) in cases {
+            let redirect = get_vhost_redirect(vhost_body, false).unwrap();
+
+            assert_eq!(redirect, Redirect { status, target: target.to_string(), keeps_host },
+                       "'{}'", vhost_body);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::apache::parser::Directive;
+use crate::apache::redirect::get_vhost_directives;
+
+const LOCATION_SECTION: &str = "Location";
+const DIRECTORY_SECTION: &str = "Directory";
+const DOCUMENT_ROOT_DIRECTIVE: &str = "DocumentRoot";
+
+const REQUIRE_DIRECTIVE: &str = "Require";
+const REQUIRE_SECTIONS: [&str; 3] = ["RequireAny", "RequireAll", "RequireNone"];
+const AUTH_TYPE_DIRECTIVE: &str = "AuthType";
+
+/// apache 2.2 access control
+const ORDER_DIRECTIVE: &str = "Order";
+const ALLOW_DIRECTIVE: &str = "Allow";
+const DENY_DIRECTIVE: &str = "Deny";
+
+/// `Require valid-user`, `Require user admin`, `Require group admins`
+const USER_REQUIRE_TYPES: [&str; 3] = ["valid-user", "user", "group"];
+
+const UNAUTHORIZED_STATUS: u16 = 401;
+const FORBIDDEN_STATUS: u16 = 403;
+
+/// Expected status of request to `/` without credentials from access control of
+/// `<Location />`, `<Directory>` with `DocumentRoot` or the virtual host itself (the first one
+/// with access directives wins, like apache merges them):
+///
+/// - `Require all denied`, `Deny from all` - 403
+/// - `Require valid-user` (`user`, `group`) with `AuthType` - 401
+///
+/// `None` for access depending on client (`Require ip ...`) or granted access.
+pub fn get_expected_status(section: &Directive) -> Option<u16> {
+    let directives = get_vhost_directives(section.children());
+
+    let document_root = directives.iter()
+        .find(|directive| directive.is(DOCUMENT_ROOT_DIRECTIVE))
+        .and_then(|directive| directive.args.first())
+        .map(|path| path.trim_end_matches('/'));
+
+    let location = directives.iter().find(|directive| {
+        directive.is(LOCATION_SECTION) && directive.args.first().is_some_and(|path| path == "/")
+    });
+
+    let directory = document_root.and_then(|document_root| directives.iter().find(|directive| {
+        directive.is(DIRECTORY_SECTION) &&
+            directive.args.first().is_some_and(|path| path.trim_end_matches('/') == document_root)
+    }));
+
+    // the most specific block first
+    let blocks: Vec<Vec<&Directive>> = location.into_iter().chain(directory)
+        .map(|block| get_vhost_directives(block.children()))
+        .chain(std::iter::once(directives.clone()))
+        .collect();
+
+    let auth_type = blocks.iter().flatten().any(|directive| directive.is(AUTH_TYPE_DIRECTIVE));
+
+    let access_directives = blocks.iter()
+        .find(|block| block.iter().any(|directive| is_access_directive(directive)))?;
+
+    get_access_status(access_directives, auth_type)
+}
+
+fn is_access_directive(directive: &Directive) -> bool {
+    [REQUIRE_DIRECTIVE, ORDER_DIRECTIVE, ALLOW_DIRECTIVE, DENY_DIRECTIVE].iter()
+        .chain(REQUIRE_SECTIONS.iter())
+        .any(|name| directive.is(name))
+}
+
+fn get_access_status(directives: &[&Directive], auth_type: bool) -> Option<u16> {
+    if directives.iter().any(|directive| REQUIRE_SECTIONS.iter().any(|name| directive.is(name))) {
+        debug!("require sections aren't supported for expected status");
+        return None;
+    }
+
+    let requires: Vec<Vec<String>> = directives.iter()
+        .filter(|directive| directive.is(REQUIRE_DIRECTIVE))
+        .map(|directive| directive.args.iter().map(|arg| arg.to_lowercase()).collect())
+        .collect();
+
+    if requires.is_empty() {
+        let deny_all = directives.iter().any(|directive| directive.is(DENY_DIRECTIVE) && is_from_all(directive));
+        let allow = directives.iter().any(|directive| directive.is(ALLOW_DIRECTIVE));
+
+        return if deny_all && !allow { Some(FORBIDDEN_STATUS) } else { None };
+    }
+
+    if requires.iter().all(|args| args.as_slice() == ["all", "denied"]) {
+        Some(FORBIDDEN_STATUS)
+
+    } else if auth_type && requires.iter().all(|args| {
+        args.first().is_some_and(|require_type| USER_REQUIRE_TYPES.contains(&require_type.as_str()))
+    }) {
+        Some(UNAUTHORIZED_STATUS)
+
+    } else {
+        None
+    }
+}
+
+/// `Deny from all`
+fn is_from_all(directive: &Directive) -> bool {
+    matches!(directive.args.as_slice(),
+             [from, all] if from.eq_ignore_ascii_case("from") && all.eq_ignore_ascii_case("all"))
+}
+
+#[cfg(test)]
+mod status_tests {
+    use crate::apache::parser::parse_config;
+    use crate::apache::status::get_expected_status;
+
+    fn get_section_status(section_body: &str) -> Option<u16> {
+        let directives = parse_config(&format!("<VirtualHost *:80>\n{section_body}\n</VirtualHost>")).unwrap();
+
+        get_expected_status(&directives[0])
+    }
+
+    #[test]
+    fn expected_status_should_be_inferred_from_access_control() {
+        let cases = [
+            ("Require all denied", Some(403)),
+            ("Require all granted", None),
+            ("AuthType Basic\nAuthName \"Restricted\"\nRequire valid-user", Some(401)),
+            ("Require valid-user", None),
+            ("Require ip 10.0.0.0/8", None),
+            ("<IfModule mod_authz_core.c>\n    Require all denied\n</IfModule>", Some(403)),
+            ("Require all denied\n<Location />\n    Require all granted\n</Location>", None),
+            ("<Location \"/\">\n    AuthType Basic\n    Require user admin\n</Location>", Some(401)),
+            ("<Location /api>\n    Require all denied\n</Location>", None),
+            ("DocumentRoot /var/www/html/\n<Directory /var/www/html>\n    Require all denied\n</Directory>", Some(403)),
+            ("<Directory /var/www/other>\n    Require all denied\n</Directory>", None),
+            ("Order deny,allow\nDeny from all", Some(403)),
+            ("Order deny,allow\nDeny from all\nAllow from 127.0.0.1", None),
+            ("<RequireAny>\n    Require all denied\n</RequireAny>", None),
+        ];
+
+        for (section_body, expected_status) in cases {
+            assert_eq!(get_section_status(section_body), expected_status, "'{}'", section_body);
+        }
+    }
+}
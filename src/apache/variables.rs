@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::apache::parser::Directive;
+
+const DEFINE_DIRECTIVE: &str = "Define";
+const UNDEFINE_DIRECTIVE: &str = "UnDefine";
+
+/// Variables for `${VAR}` references in apache configs.
+///
+/// Values from `Define` have priority over environment variables, like in apache.
+#[derive(Clone, Debug, Default)]
+pub struct Variables {
+    /// `Define NAME VALUE`
+    defined: HashMap<String, String>,
+
+    /// Parameters from `Define NAME` and `Define NAME VALUE`
+    parameters: Vec<String>,
+
+    environment: HashMap<String, String>
+}
+
+impl Variables {
+    pub fn new(environment: HashMap<String, String>) -> Variables {
+        Variables { environment, ..Default::default() }
+    }
+
+    /// Variables from process environment.
+    pub fn from_env() -> Variables {
+        Variables::new(env::vars().collect())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.defined.get(name).or_else(|| self.environment.get(name)).map(|value| value.as_str())
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.parameters.iter().any(|parameter| parameter == name)
+    }
+
+    /// Adds variables from shell file like `/etc/apache2/envvars` (`export NAME=value` lines).
+    pub fn load_envvars_file(&mut self, envvars_file: &Path) -> anyhow::Result<()> {
+        debug!("load environment variables from '{}'", envvars_file.display());
+
+        let input = fs::read_to_string(envvars_file)
+            .with_context(|| format!("couldn't read envvars file '{}'", envvars_file.display()))?;
+
+        for row in input.lines() {
+            let row = row.trim();
+            let row = row.strip_prefix("export ").unwrap_or(row).trim();
+
+            if let Some((name, value)) = row.split_once('=') {
+                let valid_name = !name.is_empty() &&
+                    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+                if valid_name && !row.starts_with('#') {
+                    let value = value.trim_matches(|c| c == '"' || c == '\'');
+                    let value = self.substitute_shell(value);
+                    trace!("environment variable {name}='{value}'");
+                    self.environment.insert(name.to_string(), value);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces `${VAR}` with values, unknown variables are left as is (apache does the same).
+    pub fn substitute(&self, value: &str) -> String {
+        let mut result = String::new();
+        let mut rest = value;
+
+        while let Some(start) = rest.find("${") {
+            result.push_str(&rest[..start]);
+
+            let reference = &rest[start..];
+
+            match reference.find('}') {
+                Some(end) => {
+                    let name = &reference[2..end];
+
+                    match self.get(name) {
+                        Some(variable_value) => result.push_str(variable_value),
+                        None => {
+                            warn!("variable '{name}' isn't defined");
+                            result.push_str(&reference[..=end]);
+                        }
+                    }
+
+                    rest = &reference[end + 1..];
+                }
+                None => {
+                    result.push_str(reference);
+                    rest = "";
+                }
+            }
+        }
+
+        result.push_str(rest);
+
+        result
+    }
+
+    /// Substitutes variables in directive arguments and tracks `Define` / `UnDefine`.
+    pub fn apply(&mut self, directive: &mut Directive) {
+        for arg in directive.args.iter_mut() {
+            if arg.contains("${") {
+                *arg = self.substitute(arg);
+            }
+        }
+
+        if directive.is(DEFINE_DIRECTIVE) && !directive.is_block() {
+            if let Some(name) = directive.args.first() {
+                if let Some(value) = directive.args.get(1) {
+                    self.defined.insert(name.to_string(), value.to_string());
+                }
+
+                if !self.is_defined(name) {
+                    self.parameters.push(name.to_string());
+                }
+            }
+
+        } else if directive.is(UNDEFINE_DIRECTIVE) && !directive.is_block() {
+            if let Some(name) = directive.args.first() {
+                self.defined.remove(name);
+                self.parameters.retain(|parameter| parameter != name);
+            }
+        }
+    }
+
+    /// `$VAR` and `${VAR}` in shell values, unknown variables are empty.
+    fn substitute_shell(&self, value: &str) -> String {
+        let mut result = String::new();
+        let mut chars = value.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                result.push(c);
+                continue;
+            }
+
+            let braces = chars.peek() == Some(&'{');
+
+            if braces {
+                chars.next();
+            }
+
+            let mut name = String::new();
+
+            while let Some(&name_char) = chars.peek() {
+                if !(name_char.is_ascii_alphanumeric() || name_char == '_') {
+                    break;
+                }
+                name.push(name_char);
+                chars.next();
+            }
+
+            if braces {
+                // modifiers like `${VAR##prefix}` aren't supported, skip them
+                for skipped_char in chars.by_ref() {
+                    if skipped_char == '}' {
+                        break;
+                    }
+                }
+            }
+
+            if name.is_empty() {
+                result.push(c);
+
+            } else if let Some(variable_value) = self.environment.get(&name) {
+                result.push_str(variable_value);
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod variables_tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use crate::apache::context::{ConfigContext, evaluate_config};
+    use crate::apache::parser::parse_config;
+    use crate::apache::variables::Variables;
+
+    fn get_variables() -> Variables {
+        let environment = HashMap::from([
+            ("APACHE_LOG_DIR".to_string(), "/var/log/apache2".to_string()),
+            ("DOMAIN".to_string(), "env.example.com".to_string()),
+        ]);
+
+        Variables::new(environment)
+    }
+
+    #[test]
+    fn variables_should_be_substituted_in_config_order() {
+        let input = "ServerName ${DOMAIN}\nDefine DOMAIN example.com\nDefine PORT 8080\n\
+                     <VirtualHost *:${PORT}>\n    ServerName ${DOMAIN}\n    \
+                     ErrorLog ${APACHE_LOG_DIR}/error.log\n</VirtualHost>\n\
+                     UnDefine DOMAIN\nServerName ${DOMAIN}\nServerName ${UNKNOWN}";
+
+        let mut context = ConfigContext::new(get_variables(), &[]);
+        let directives = evaluate_config(parse_config(input).unwrap(), &mut context);
+
+        let args: Vec<&str> = directives.iter()
+            .filter(|directive| directive.is("ServerName"))
+            .map(|directive| directive.args[0].as_str()).collect();
+
+        assert_eq!(args, vec!["env.example.com", "env.example.com", "${UNKNOWN}"]);
+
+        let vhost = directives.iter().find(|directive| directive.is("VirtualHost")).unwrap();
+        assert_eq!(vhost.args, vec!["*:8080"]);
+        assert_eq!(vhost.children()[0].args, vec!["example.com"]);
+        assert_eq!(vhost.children()[1].args, vec!["/var/log/apache2/error.log"]);
+    }
+
+    #[test]
+    fn parameters_without_value_should_be_defined() {
+        let mut context = ConfigContext::new(get_variables(), &[]);
+
+        evaluate_config(parse_config("Define SSL\nDefine TEMP 1\nUnDefine TEMP").unwrap(),
+                        &mut context);
+
+        let variables = context.variables;
+
+        assert!(variables.is_defined("SSL"));
+        assert!(!variables.is_defined("TEMP"));
+        assert_eq!(variables.get("SSL"), None);
+        assert_eq!(variables.get("TEMP"), None);
+    }
+
+    #[test]
+    fn envvars_file_should_be_loaded() {
+        let mut variables = Variables::new(HashMap::new());
+
+        variables.load_envvars_file(Path::new("test-data/apache-root/envvars")).unwrap();
+
+        assert_eq!(variables.get("APACHE_RUN_USER"), Some("www-data"));
+        assert_eq!(variables.get("APACHE_LOG_DIR"), Some("/var/log/apache2"));
+        assert_eq!(variables.get("APACHE_RUN_DIR"), Some("/var/run/apache2-test"));
+        assert_eq!(variables.get("SITE_DOMAIN"), Some("envvars.example.com"));
+    }
+}
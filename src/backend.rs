@@ -0,0 +1,65 @@
+use crate::domain::{BackendEndpoint, VirtualHost};
+use crate::webserver::parse_listen_address;
+
+/// Backend endpoints of all vhosts, duplicates are skipped.
+pub fn get_backend_endpoints(vhosts: &[VirtualHost]) -> Vec<BackendEndpoint> {
+    let mut endpoints: Vec<BackendEndpoint> = Vec::new();
+
+    for backend in vhosts.iter().flat_map(|vhost| &vhost.backends) {
+        let duplicate = endpoints.iter().any(
+            |endpoint| endpoint.backend == backend.address && endpoint.proto == backend.proto);
+
+        if duplicate {
+            debug!("backend '{}' ({}) is already added, skip", backend.address, backend.proto);
+            continue;
+        }
+
+        let listen_address = parse_listen_address(&backend.address);
+
+        endpoints.push(BackendEndpoint {
+            backend: backend.address.to_string(),
+            proto: backend.proto.to_string(),
+            host: listen_address.as_ref().and_then(|listen_address| listen_address.address.clone()),
+            port: listen_address.and_then(|listen_address| listen_address.port),
+            upstream: backend.upstream.clone()
+        });
+    }
+
+    endpoints
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use crate::backend::get_backend_endpoints;
+    use crate::domain::{Backend, VirtualHost};
+
+    fn backend(proto: &str, address: &str) -> Backend {
+        Backend { proto: proto.to_string(), address: address.to_string(), upstream: None }
+    }
+
+    #[test]
+    fn backend_endpoints_should_be_unique_with_host_and_port() {
+        let vhosts = vec![
+            VirtualHost {
+                domain: "example.com".to_string(), port: 80,
+                backends: vec![backend("http", "10.0.0.1:8080"), backend("fastcgi", "unix:/run/php.sock")],
+                ..Default::default()
+            },
+            VirtualHost {
+                domain: "example.org".to_string(), port: 443,
+                backends: vec![backend("http", "10.0.0.1:8080"), backend("grpc", "[::1]:50051")],
+                ..Default::default()
+            },
+        ];
+
+        let endpoints: Vec<(String, String, Option<String>, Option<i32>)> = get_backend_endpoints(&vhosts)
+            .into_iter().map(|endpoint| (endpoint.backend, endpoint.proto, endpoint.host, endpoint.port))
+            .collect();
+
+        assert_eq!(endpoints, vec![
+            ("10.0.0.1:8080".to_string(), "http".to_string(), Some("10.0.0.1".to_string()), Some(8080)),
+            ("unix:/run/php.sock".to_string(), "fastcgi".to_string(), None, None),
+            ("[::1]:50051".to_string(), "grpc".to_string(), Some("::1".to_string()), Some(50051)),
+        ]);
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+
+use crate::domain::{Certificate, CertificateFile, VirtualHost};
+
+const SECONDS_IN_DAY: i64 = 24 * 60 * 60;
+
+const CERTIFICATE_PEM_LABEL: &str = "CERTIFICATE";
+
+/// Reads certificate files of vhosts, every file is read once.
+/// Vhost keeps certificate path only if file couldn't be read.
+pub fn read_certificates(vhosts: &[VirtualHost]) -> Vec<VirtualHost> {
+    let mut certificates: HashMap<String, Option<Certificate>> = HashMap::new();
+
+    vhosts.iter()
+        .map(|vhost| {
+            let certificate = vhost.certificate.as_ref().map(|certificate| {
+                let loaded_certificate = certificates.entry(certificate.path.to_string())
+                    .or_insert_with(|| {
+                        match read_certificate_file(Path::new(&certificate.path)) {
+                            Ok(loaded_certificate) => {
+                                debug!("certificate '{}' loaded: {:?}", certificate.path, loaded_certificate);
+                                Some(loaded_certificate)
+                            }
+                            Err(e) => {
+                                warn!("couldn't read certificate '{}': {:#}", certificate.path, e);
+                                None
+                            }
+                        }
+                    });
+
+                loaded_certificate.clone().unwrap_or_else(|| certificate.clone())
+            });
+
+            VirtualHost { certificate, ..vhost.clone() }
+        })
+        .collect()
+}
+
+/// First certificate of PEM file is a leaf certificate, the rest is a chain.
+/// Other blocks are skipped, e.g. private key of combined key and certificate file.
+fn read_certificate_file(path: &Path) -> anyhow::Result<Certificate> {
+    let data = fs::read(path).context("couldn't read file")?;
+
+    let pem = Pem::iter_from_buffer(&data)
+        .find(|pem| pem.as_ref().map_or(true, |pem| pem.label == CERTIFICATE_PEM_LABEL))
+        .context("certificate not found")?
+        .context("invalid PEM")?;
+
+    let x509 = pem.parse_x509().context("invalid certificate")?;
+
+    let mut sans: Vec<String> = Vec::new();
+
+    if let Ok(Some(extension)) = x509.subject_alternative_name() {
+        for name in &extension.value.general_names {
+            if let GeneralName::DNSName(name) = name {
+                sans.push(name.to_lowercase());
+            }
+        }
+    }
+
+    Ok(Certificate {
+        path: path.display().to_string(),
+        not_after: Some(x509.validity().not_after.timestamp()),
+        sans,
+        subject: x509.subject().to_string(),
+        issuer: x509.issuer().to_string()
+    })
+}
+
+/// Unique certificate files of vhosts, files which couldn't be read are skipped.
+/// `now` is unix timestamp for days left calculation.
+pub fn get_certificate_files(vhosts: &[VirtualHost], now: i64) -> Vec<CertificateFile> {
+    let mut certificate_files: Vec<CertificateFile> = Vec::new();
+
+    for vhost in vhosts {
+        let (certificate, not_after) = match &vhost.certificate {
+            Some(certificate) => match certificate.not_after {
+                Some(not_after) => (certificate, not_after),
+                None => continue
+            },
+            None => continue
+        };
+
+        match certificate_files.iter_mut().find(|file| file.path == certificate.path) {
+            Some(certificate_file) => {
+                if !vhost.domain.is_empty() &&
+                    !certificate_file.names.split(',').any(|name| name == vhost.domain) {
+                    certificate_file.names.push(',');
+                    certificate_file.names.push_str(&vhost.domain);
+                }
+            }
+            None => certificate_files.push(CertificateFile {
+                path: certificate.path.to_string(),
+                subject: certificate.subject.to_string(),
+                issuer: certificate.issuer.to_string(),
+                sans: certificate.sans.join(","),
+                not_after,
+                days_left: (not_after - now).div_euclid(SECONDS_IN_DAY),
+                names: vhost.domain.to_string()
+            })
+        }
+    }
+
+    certificate_files
+}
+
+/// Logs warning for every vhost name which isn't covered by certificate SANs.
+pub fn check_certificate_names(vhosts: &[VirtualHost]) {
+    for vhost in vhosts {
+        let certificate = match &vhost.certificate {
+            Some(certificate) if certificate.not_after.is_some() => certificate,
+            _ => continue
+        };
+
+        let names = std::iter::once(&vhost.domain).chain(vhost.aliases.iter())
+            .filter(|name| !name.is_empty());
+
+        for name in names {
+            if !is_name_covered(name, &certificate.sans) {
+                warn!("name '{name}' (port {}) isn't covered by certificate '{}', SANs: {:?}",
+                      vhost.port, certificate.path, certificate.sans);
+            }
+        }
+    }
+}
+
+/// Wildcard SAN `*.example.com` covers exactly one label: `www.example.com`, but not `example.com`.
+/// Names are compared in ASCII form, as SANs store international names in punycode.
+fn is_name_covered(name: &str, sans: &[String]) -> bool {
+    let name = idna::domain_to_ascii(name).unwrap_or_else(|_| name.to_string());
+
+    sans.iter().any(|san| {
+        match san.strip_prefix("*.") {
+            Some(san_base) => name.split_once('.')
+                .is_some_and(|(label, base)| !label.is_empty() && base == san_base),
+            None => *san == name
+        }
+    })
+}
+
+#[cfg(test)]
+mod certificate_tests {
+    use std::path::Path;
+
+    use crate::certificate::{get_certificate_files, is_name_covered, read_certificate_file, read_certificates};
+    use crate::domain::{Certificate, VirtualHost};
+
+    const CERTIFICATE_PATH: &str = "test-data/certificates/example.com.pem";
+
+    #[test]
+    fn leaf_certificate_expiry_and_sans_should_be_read() {
+        let certificate = read_certificate_file(Path::new(CERTIFICATE_PATH)).unwrap();
+
+        assert_eq!(certificate.path, CERTIFICATE_PATH);
+        assert_eq!(certificate.not_after, Some(2051222400));
+        assert_eq!(certificate.sans, vec!["example.com", "*.example.com"]);
+        assert_eq!(certificate.subject, "CN=example.com");
+        assert_eq!(certificate.issuer, "CN=example.com");
+    }
+
+    #[test]
+    fn private_key_before_certificate_should_be_skipped() {
+        let path = "test-data/certificates/example.com-with-key.pem";
+
+        let certificate = read_certificate_file(Path::new(path)).unwrap();
+
+        assert_eq!(certificate.not_after, Some(2051222400));
+        assert_eq!(certificate.subject, "CN=example.com");
+    }
+
+    #[test]
+    fn vhost_should_keep_certificate_path_if_file_is_missing() {
+        let certificate = |path: &str| Some(Certificate { path: path.to_string(), ..Default::default() });
+
+        let vhosts = vec![
+            VirtualHost { domain: "example.com".to_string(), certificate: certificate(CERTIFICATE_PATH), ..Default::default() },
+            VirtualHost { domain: "example.org".to_string(), certificate: certificate("missing.pem"), ..Default::default() },
+            VirtualHost { domain: "example.net".to_string(), ..Default::default() },
+        ];
+
+        let vhosts = read_certificates(&vhosts);
+
+        assert_eq!(vhosts[0].certificate.as_ref().unwrap().not_after, Some(2051222400));
+        assert_eq!(vhosts[1].certificate, certificate("missing.pem"));
+        assert_eq!(vhosts[2].certificate, None);
+    }
+
+    #[test]
+    fn names_should_be_matched_with_wildcard_sans() {
+        let sans = vec!["example.com".to_string(), "*.example.com".to_string(),
+                        "xn--e1afmkfd.xn--p1ai".to_string()];
+
+        assert!(is_name_covered("example.com", &sans));
+        assert!(is_name_covered("www.example.com", &sans));
+        assert!(is_name_covered("пример.рф", &sans));
+        assert!(!is_name_covered("a.b.example.com", &sans));
+        assert!(!is_name_covered("example.org", &sans));
+    }
+
+    #[test]
+    fn certificate_files_should_be_unique_with_vhost_names() {
+        let certificate = |path: &str| Some(Certificate { path: path.to_string(), ..Default::default() });
+
+        let vhosts = read_certificates(&[
+            VirtualHost { domain: "example.com".to_string(), port: 443, certificate: certificate(CERTIFICATE_PATH), ..Default::default() },
+            VirtualHost { domain: "example.com".to_string(), port: 8443, certificate: certificate(CERTIFICATE_PATH), ..Default::default() },
+            VirtualHost { domain: "www.example.com".to_string(), port: 443, certificate: certificate(CERTIFICATE_PATH), ..Default::default() },
+            VirtualHost { domain: "example.org".to_string(), port: 443, certificate: certificate("missing.pem"), ..Default::default() },
+        ]);
+
+        let certificate_files = get_certificate_files(&vhosts, 2051222400 - 10 * 24 * 60 * 60 - 1);
+
+        assert_eq!(certificate_files.len(), 1);
+
+        let certificate_file = &certificate_files[0];
+        assert_eq!(certificate_file.path, CERTIFICATE_PATH);
+        assert_eq!(certificate_file.names, "example.com,www.example.com");
+        assert_eq!(certificate_file.days_left, 10);
+
+        let expired_files = get_certificate_files(&vhosts, 2051222400 + 1);
+        assert_eq!(expired_files[0].days_left, -1);
+    }
+}
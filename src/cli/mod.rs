@@ -1,6 +1,27 @@
 use clap::ArgMatches;
 
-use crate::{DOMAIN_IGNORE_MASKS_OPTION, FALL_ON_PARSE_ERROR_OPTION, FALL_ON_PARSE_ERROR_SHORT_OPTION, INCLUDE_CUSTOM_PORTS_OPTION, INCLUDE_DOMAINS_WITH_WWW, RECURSIVE_OPTION, VHOST_FILE_EXTENSIONS_DEFAULT_VALUE};
+use crate::{ALL_SERVER_NAMES_OPTION, APACHE_MODULES_ARGUMENT, CHECK_CERTIFICATE_NAMES_OPTION, DEFAULT_SERVERS_ARGUMENT, DEFAULT_SERVERS_HOSTNAME_VALUE, DEFAULT_SERVERS_IP_VALUE, DISCOVERY_TYPE_ARGUMENT, DISCOVERY_TYPE_BACKENDS_VALUE, DISCOVERY_TYPE_CERTIFICATES_VALUE, DISCOVERY_TYPE_REDIRECTS_VALUE, DISCOVERY_TYPE_STREAMS_VALUE, DOMAIN_IGNORE_MASKS_OPTION, FALL_ON_PARSE_ERROR_OPTION, IDN_FORMAT_ARGUMENT, IDN_FORMAT_PUNYCODE_VALUE, IDN_FORMAT_UNICODE_VALUE, FALL_ON_PARSE_ERROR_SHORT_OPTION, INCLUDE_CUSTOM_PORTS_OPTION, INCLUDE_DOMAINS_WITH_WWW, RECURSIVE_OPTION, VHOST_FILE_EXTENSIONS_DEFAULT_VALUE, WILDCARD_NAMES_ARGUMENT, WILDCARD_NAMES_BASE_DOMAIN_VALUE, WILDCARD_NAMES_EXPAND_VALUE, WILDCARD_SUBDOMAINS_ARGUMENT};
+use crate::filter::{DefaultServerPolicy, WildcardPolicy};
+use crate::site::IdnFormat;
+
+/// What to emit in discovery output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiscoveryType {
+    /// Sites without redirect
+    Vhosts,
+
+    /// Vhosts with redirect, with target url and expected status
+    Redirects,
+
+    /// nginx stream (TCP/UDP) servers
+    Streams,
+
+    /// Backend endpoints of nginx vhosts: upstream servers, `proxy_pass`, `fastcgi_pass`, etc.
+    Backends,
+
+    /// Unique certificate files of vhosts with expiry
+    Certificates
+}
 
 pub struct AppConfig {
     pub fall_on_parse_errors: bool,
@@ -8,8 +29,25 @@ pub struct AppConfig {
     pub include_custom_domains: bool,
     pub recursive_mode: bool,
 
+    /// Add vhost for every server name and alias, not only the first one
+    pub all_server_names: bool,
+
+    /// Warn about vhost names which aren't covered by certificate SANs
+    pub check_certificate_names: bool,
+
+    pub wildcard_policy: WildcardPolicy,
+
+    pub default_server_policy: DefaultServerPolicy,
+
+    pub idn_format: IdnFormat,
+
+    pub discovery_type: DiscoveryType,
+
     pub domain_ignore_masks: Vec<String>,
 
+    /// Loaded apache modules supplied by user, empty if not set
+    pub apache_modules: Vec<String>,
+
     pub vhost_file_extensions: Vec<String>
 }
 
@@ -31,11 +69,65 @@ pub fn get_app_config(arg_matches: &ArgMatches) -> AppConfig {
         include_domains_with_www: arg_matches.occurrences_of(INCLUDE_DOMAINS_WITH_WWW) > 0,
         include_custom_domains: arg_matches.occurrences_of(INCLUDE_CUSTOM_PORTS_OPTION) > 0,
         recursive_mode: arg_matches.occurrences_of(RECURSIVE_OPTION) > 0,
+        all_server_names: arg_matches.occurrences_of(ALL_SERVER_NAMES_OPTION) > 0,
+        check_certificate_names: arg_matches.occurrences_of(CHECK_CERTIFICATE_NAMES_OPTION) > 0,
+        wildcard_policy: get_wildcard_policy(arg_matches.value_of(WILDCARD_NAMES_ARGUMENT),
+                                             arg_matches.value_of(WILDCARD_SUBDOMAINS_ARGUMENT)),
+        default_server_policy: get_default_server_policy(arg_matches.value_of(DEFAULT_SERVERS_ARGUMENT)),
+        idn_format: get_idn_format(arg_matches.value_of(IDN_FORMAT_ARGUMENT)),
+        discovery_type: get_discovery_type(arg_matches.value_of(DISCOVERY_TYPE_ARGUMENT)),
         domain_ignore_masks: get_string_args_separated_by_comma(domain_ignore_masks_row),
+        apache_modules: arg_matches.value_of(APACHE_MODULES_ARGUMENT)
+                                   .map(get_string_args_separated_by_comma).unwrap_or_default(),
         vhost_file_extensions,
     }
 }
 
+fn get_wildcard_policy(value: Option<&str>, subdomains: Option<&str>) -> WildcardPolicy {
+    match value {
+        Some(WILDCARD_NAMES_BASE_DOMAIN_VALUE) => WildcardPolicy::BaseDomain,
+        Some(WILDCARD_NAMES_EXPAND_VALUE) => {
+            let subdomains: Vec<String> = subdomains.map(get_string_args_separated_by_comma)
+                .unwrap_or_default().into_iter()
+                .filter(|subdomain| !subdomain.is_empty()).collect();
+
+            if subdomains.is_empty() {
+                warn!("known subdomains aren't set for wildcard names expand, use --{}",
+                      WILDCARD_SUBDOMAINS_ARGUMENT);
+            }
+
+            WildcardPolicy::Expand(subdomains)
+        }
+        _ => WildcardPolicy::Skip
+    }
+}
+
+fn get_default_server_policy(value: Option<&str>) -> DefaultServerPolicy {
+    match value {
+        Some(DEFAULT_SERVERS_HOSTNAME_VALUE) => DefaultServerPolicy::HostName,
+        Some(DEFAULT_SERVERS_IP_VALUE) => DefaultServerPolicy::Ip,
+        _ => DefaultServerPolicy::Skip
+    }
+}
+
+fn get_idn_format(value: Option<&str>) -> IdnFormat {
+    match value {
+        Some(IDN_FORMAT_PUNYCODE_VALUE) => IdnFormat::Punycode,
+        Some(IDN_FORMAT_UNICODE_VALUE) => IdnFormat::Unicode,
+        _ => IdnFormat::UnicodeName
+    }
+}
+
+fn get_discovery_type(value: Option<&str>) -> DiscoveryType {
+    match value {
+        Some(DISCOVERY_TYPE_REDIRECTS_VALUE) => DiscoveryType::Redirects,
+        Some(DISCOVERY_TYPE_STREAMS_VALUE) => DiscoveryType::Streams,
+        Some(DISCOVERY_TYPE_BACKENDS_VALUE) => DiscoveryType::Backends,
+        Some(DISCOVERY_TYPE_CERTIFICATES_VALUE) => DiscoveryType::Certificates,
+        _ => DiscoveryType::Vhosts
+    }
+}
+
 fn get_string_args_separated_by_comma(input: &str) -> Vec<String> {
     input.split(",")
         .collect::<Vec<&str>>()
@@ -1,11 +1,132 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
 use serde::Serialize;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct VirtualHost {
     pub domain: String,
-    pub port: i32
+    pub port: i32,
+
+    /// HTTPS: nginx `listen ... ssl`, `ssl on`, apache `SSLEngine on`
+    pub tls: bool,
+
+    /// Bind address from nginx `listen` or apache `<VirtualHost>`, `None` for all addresses
+    pub address: Option<String>,
+
+    /// Other names of the same vhost, e.g. rest of nginx `server_name` values
+    pub aliases: Vec<String>,
+
+    /// The whole vhost redirects to another url: nginx `return 301 https://...`, apache `Redirect`
+    pub redirect: Option<Redirect>,
+
+    /// Catch-all server without names: nginx `server_name _;`, `listen 80 default_server;`.
+    /// Domain is empty until it's resolved with host name or IP
+    pub default_server: bool,
+
+    /// Application servers behind the vhost: nginx `proxy_pass`, `fastcgi_pass`, etc.
+    pub backends: Vec<Backend>,
+
+    /// nginx `ssl_certificate`, apache `SSLCertificateFile`
+    pub certificate: Option<Certificate>,
+
+    /// Monitoring paths: `# vhdt: path=/healthz` or nginx `location = /healthz`, site root if empty
+    pub paths: Vec<String>,
+
+    /// Human readable name from `# vhdt: name=...`
+    pub display_name: Option<String>,
+
+    /// Expected response status from `# vhdt: expect=...`, redirect status is used otherwise
+    pub expected_status: Option<u16>,
+
+    /// `# vhdt: tags=team:payments`
+    pub tags: Vec<String>,
+
+    /// Redirect and expected status evaluated for exact aliases, they may differ from the primary
+    /// name: nginx `if ($host = ...)`, apache `RewriteCond %{HTTP_HOST} ...`
+    pub alias_responses: HashMap<String, NameResponse>
+}
+
+/// Redirect and expected status of the vhost for a single name
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NameResponse {
+    pub redirect: Option<Redirect>,
+    pub expected_status: Option<u16>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Certificate {
+    pub path: String,
+
+    /// Leaf certificate expiry (unix timestamp), `None` until certificate file is read
+    pub not_after: Option<i64>,
+
+    /// DNS names from Subject Alternative Name extension
+    pub sans: Vec<String>,
+
+    /// Distinguished names: `CN=example.com`, empty until certificate file is read
+    pub subject: String,
+    pub issuer: String
+}
+
+/// Host of default server redirect target until default server domain is resolved:
+/// `return 301 https://$host$request_uri;` -> `https://$host/`
+pub const UNRESOLVED_HOST: &str = "$host";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redirect {
+    pub status: u16,
+
+    /// Target url for request to `/`
+    pub target: String,
+
+    /// Target host is taken from request: nginx `$host`, apache `%{HTTP_HOST}`
+    pub keeps_host: bool
+}
+
+impl Redirect {
+    /// Redirect for another name of the same vhost, e.g. alias
+    pub fn for_domain(&self, domain: &str, other_domain: &str) -> Redirect {
+        let target = if self.keeps_host {
+            self.target.replacen(&format!("://{domain}"), &format!("://{other_domain}"), 1)
+        } else {
+            self.target.to_string()
+        };
+
+        Redirect { target, ..self.clone() }
+    }
+
+    /// Redirect of default server with resolved domain
+    pub fn for_default_server(&self, domain: &str) -> Redirect {
+        Redirect { target: self.target.replace(UNRESOLVED_HOST, domain), ..self.clone() }
+    }
+}
+
+/// nginx `stream` server: `listen 5432; proxy_pass db;`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StreamServer {
+    pub port: i32,
+
+    /// `listen ... udp`, TCP otherwise
+    pub udp: bool,
+
+    pub address: Option<String>,
+
+    /// `proxy_pass` value: upstream name or address
+    pub upstream: Option<String>
+}
+
+/// Backend endpoint from nginx `proxy_pass http://app;` with `upstream app { server 10.0.0.1:8080; }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backend {
+    /// `http`, `https`, `fastcgi`, `uwsgi`, `grpc`, `grpcs`
+    pub proto: String,
+
+    /// `host:port` or `unix:/path/to/socket`
+    pub address: String,
+
+    /// Upstream name if address was taken from `upstream` block
+    pub upstream: Option<String>
 }
 
 impl Display for VirtualHost {
@@ -20,4 +141,68 @@ pub struct Site {
     pub name: String,
     #[serde(rename(serialize = "{#URL}"))]
     pub url: String,
-}
\ No newline at end of file
+    #[serde(rename(serialize = "{#PATH}"), skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(rename(serialize = "{#ADDRESS}"), skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(rename(serialize = "{#REDIRECT_TO}"), skip_serializing_if = "Option::is_none")]
+    pub redirect_to: Option<String>,
+    #[serde(rename(serialize = "{#EXPECTED_STATUS}"), skip_serializing_if = "Option::is_none")]
+    pub expected_status: Option<u16>,
+    #[serde(rename(serialize = "{#DISPLAY_NAME}"), skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(rename(serialize = "{#TAGS}"), skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    #[serde(rename(serialize = "{#CERT_PATH}"), skip_serializing_if = "Option::is_none")]
+    pub cert_path: Option<String>,
+    #[serde(rename(serialize = "{#CERT_NOT_AFTER}"), skip_serializing_if = "Option::is_none")]
+    pub cert_not_after: Option<i64>,
+    #[serde(rename(serialize = "{#CERT_SANS}"), skip_serializing_if = "Option::is_none")]
+    pub cert_sans: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Stream {
+    #[serde(rename(serialize = "{#PORT}"))]
+    pub port: i32,
+    #[serde(rename(serialize = "{#PROTO}"))]
+    pub proto: String,
+    #[serde(rename(serialize = "{#ADDRESS}"), skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(rename(serialize = "{#UPSTREAM}"), skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BackendEndpoint {
+    #[serde(rename(serialize = "{#BACKEND}"))]
+    pub backend: String,
+    #[serde(rename(serialize = "{#PROTO}"))]
+    pub proto: String,
+    #[serde(rename(serialize = "{#HOST}"), skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(rename(serialize = "{#PORT}"), skip_serializing_if = "Option::is_none")]
+    pub port: Option<i32>,
+    #[serde(rename(serialize = "{#UPSTREAM}"), skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+}
+
+/// Certificate file shared by vhosts
+#[derive(Clone, Serialize)]
+pub struct CertificateFile {
+    #[serde(rename(serialize = "{#CERT_PATH}"))]
+    pub path: String,
+    #[serde(rename(serialize = "{#CERT_SUBJECT}"))]
+    pub subject: String,
+    #[serde(rename(serialize = "{#CERT_ISSUER}"))]
+    pub issuer: String,
+    #[serde(rename(serialize = "{#CERT_SANS}"))]
+    pub sans: String,
+    #[serde(rename(serialize = "{#CERT_NOT_AFTER}"))]
+    pub not_after: i64,
+    #[serde(rename(serialize = "{#CERT_DAYS_LEFT}"))]
+    pub days_left: i64,
+    /// Names of vhosts which use the certificate
+    #[serde(rename(serialize = "{#NAMES}"))]
+    pub names: String,
+}
@@ -1,10 +1,14 @@
-use regex::Regex;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use regex::{Regex, RegexBuilder};
 
 use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
 use crate::domain::VirtualHost;
+use crate::host::{get_host_fqdn, get_host_ip};
 
-pub fn filter_by_domain_masks(vhosts: &Vec<VirtualHost>,
-                              mask_patterns: &Vec<String>) -> Vec<VirtualHost> {
+pub fn filter_by_domain_masks(vhosts: &[VirtualHost],
+                              mask_patterns: &[String]) -> Vec<VirtualHost> {
 
     let mut results: Vec<VirtualHost> = Vec::new();
 
@@ -14,7 +18,7 @@ pub fn filter_by_domain_masks(vhosts: &Vec<VirtualHost>,
         for mask in mask_patterns {
             debug!("mask regexp '{mask}'");
 
-            if mask.len() > 0 {
+            if !mask.is_empty() {
                 match Regex::new(mask) {
                     Ok(mask_pattern) => {
                         if mask_pattern.is_match(&vhost.domain) {
@@ -37,10 +41,10 @@ pub fn filter_by_domain_masks(vhosts: &Vec<VirtualHost>,
         }
     }
 
-    return results
+    results
 }
 
-pub fn filter_vhosts(vhosts: &Vec<VirtualHost>, include_custom_domains: bool) -> Vec<VirtualHost> {
+pub fn filter_vhosts(vhosts: &[VirtualHost], include_custom_domains: bool) -> Vec<VirtualHost> {
     let mut results: Vec<VirtualHost> = Vec::new();
 
     for vhost in vhosts {
@@ -50,10 +54,296 @@ pub fn filter_vhosts(vhosts: &Vec<VirtualHost>, include_custom_domains: bool) ->
         }
     }
 
-    return results
+    results
+}
+
+/// Returns vhosts with redirect if `redirects` is true, otherwise vhosts without redirect.
+pub fn filter_redirects(vhosts: &[VirtualHost], redirects: bool) -> Vec<VirtualHost> {
+    vhosts.iter()
+        .filter(|vhost| vhost.redirect.is_some() == redirects)
+        .cloned()
+        .collect()
+}
+
+/// How to report catch-all default servers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DefaultServerPolicy {
+    Skip,
+
+    /// Use host FQDN as domain
+    HostName,
+
+    /// Use listen address or host IP as domain
+    Ip
+}
+
+/// Sets domain for default servers by policy, default servers without domain are skipped.
+pub fn resolve_default_servers(vhosts: &[VirtualHost], policy: DefaultServerPolicy) -> Vec<VirtualHost> {
+    let host_name = match policy {
+        DefaultServerPolicy::HostName if vhosts.iter().any(|vhost| vhost.default_server) => {
+            get_host_fqdn()
+        }
+        _ => None
+    };
+
+    let mut results: Vec<VirtualHost> = Vec::new();
+
+    for vhost in vhosts {
+        if !vhost.default_server {
+            results.push(vhost.clone());
+            continue;
+        }
+
+        let domain = match policy {
+            DefaultServerPolicy::Skip => None,
+            DefaultServerPolicy::HostName => host_name.clone(),
+            DefaultServerPolicy::Ip => get_default_server_ip(vhost)
+        };
+
+        match domain {
+            Some(domain) => {
+                debug!("+ add default server with port {} as '{domain}'", vhost.port);
+
+                let redirect = vhost.redirect.as_ref().map(|redirect| redirect.for_default_server(&domain));
+
+                results.push(VirtualHost { domain, redirect, ..vhost.clone() });
+            }
+            None => debug!("default server with port {} has been skipped", vhost.port)
+        }
+    }
+
+    results
+}
+
+/// Listen address if it's specified, otherwise host IP. IPv6 address is enclosed in brackets for url.
+fn get_default_server_ip(vhost: &VirtualHost) -> Option<String> {
+    let listen_ip = vhost.address.as_ref()
+        .and_then(|address| address.parse::<IpAddr>().ok())
+        .filter(|ip| !ip.is_unspecified());
+
+    let ip = match listen_ip {
+        Some(ip) => ip.to_string(),
+        None => get_host_ip()?
+    };
+
+    if ip.contains(':') { Some(format!("[{ip}]")) } else { Some(ip) }
+}
+
+/// How to handle names with wildcards (`*.example.com`) and nginx regex names (`~^(?<sub>.+)\.example\.com$`)
+#[derive(Clone, Debug, PartialEq)]
+pub enum WildcardPolicy {
+    Skip,
+
+    /// Use domain after the last wildcard label: `*.example.com` -> `example.com`
+    BaseDomain,
+
+    /// Use known subdomains which match the name: `*.example.com` + `www,api` ->
+    /// `www.example.com`, `api.example.com`
+    Expand(Vec<String>)
+}
+
+/// Replaces wildcard and regex primary names by policy, vhost is added for every resolved name.
+/// If the name is skipped by policy, the first exact alias becomes the primary name.
+pub fn resolve_wildcard_names(vhosts: &[VirtualHost], wildcard_policy: &WildcardPolicy) -> Vec<VirtualHost> {
+    let mut results: Vec<VirtualHost> = Vec::new();
+
+    for vhost in vhosts {
+        if !is_wildcard_name(&vhost.domain) {
+            results.push(vhost.clone());
+            continue;
+        }
+
+        let domains = get_domains_for_wildcard_policy(&vhost.domain, wildcard_policy);
+
+        if domains.is_empty() {
+            debug!("name '{}' has been skipped by wildcard policy", vhost.domain);
+
+            if let Some(alias) = vhost.aliases.iter().find(|alias| !is_wildcard_name(alias)) {
+                debug!("+ add vhost '{alias}' as primary name instead of '{}'", vhost.domain);
+
+                let aliases = vhost.aliases.iter().filter(|name| *name != alias).cloned().collect();
+
+                results.push(VirtualHost {
+                    aliases, alias_responses: vhost.alias_responses.clone(), ..get_alias_vhost(vhost, alias)
+                });
+            }
+        }
+
+        for (index, domain) in domains.into_iter().enumerate() {
+            debug!("+ add vhost '{domain}' for name '{}'", vhost.domain);
+
+            let redirect = vhost.redirect.as_ref().map(
+                |redirect| redirect.for_domain(&vhost.domain, &domain));
+
+            // aliases are kept once, otherwise they are expanded for every name
+            let aliases = if index == 0 { vhost.aliases.clone() } else { Vec::new() };
+
+            results.push(VirtualHost { domain, aliases, redirect, ..vhost.clone() });
+        }
+    }
+
+    results
+}
+
+/// Adds vhost for every alias. Alias is skipped if the same domain and port is served
+/// by another vhost as a primary name.
+pub fn expand_aliases(vhosts: &[VirtualHost], wildcard_policy: &WildcardPolicy) -> Vec<VirtualHost> {
+    let mut results: Vec<VirtualHost> = Vec::new();
+
+    for vhost in vhosts {
+        results.push(VirtualHost { aliases: Vec::new(), ..vhost.clone() });
+
+        for alias in &vhost.aliases {
+            let alias_domains = get_domains_for_wildcard_policy(alias, wildcard_policy);
+
+            if alias_domains.is_empty() {
+                debug!("alias '{alias}' has been skipped by wildcard policy");
+            }
+
+            for alias_domain in alias_domains {
+                if vec_contains_same_domain_with_port(vhosts, &alias_domain, vhost.port) ||
+                    vec_contains_same_domain_with_port(&results, &alias_domain, vhost.port) {
+                    debug!("alias '{alias_domain}' with port {} is already served, skip", vhost.port);
+                    continue;
+                }
+
+                debug!("+ add vhost for alias '{alias_domain}'");
+                results.push(get_alias_vhost(vhost, &alias_domain));
+            }
+        }
+    }
+
+    results
+}
+
+/// Vhost for another name with redirect and expected status evaluated by parser for the alias.
+/// Wildcard aliases weren't evaluated, they get primary name response.
+fn get_alias_vhost(vhost: &VirtualHost, alias: &str) -> VirtualHost {
+    let (redirect, expected_status) = match vhost.alias_responses.get(alias) {
+        Some(response) => (response.redirect.clone(), response.expected_status),
+        None => (
+            vhost.redirect.as_ref().map(|redirect| redirect.for_domain(&vhost.domain, alias)),
+            vhost.expected_status
+        )
+    };
+
+    VirtualHost {
+        domain: alias.to_string(), aliases: Vec::new(), redirect, expected_status,
+        alias_responses: HashMap::new(), ..vhost.clone()
+    }
+}
+
+fn get_domains_for_wildcard_policy(name: &str, wildcard_policy: &WildcardPolicy) -> Vec<String> {
+    if !is_wildcard_name(name) {
+        return vec![name.to_string()];
+    }
+
+    match wildcard_policy {
+        WildcardPolicy::Skip => Vec::new(),
+        WildcardPolicy::BaseDomain => get_base_domain(name).into_iter().collect(),
+        WildcardPolicy::Expand(subdomains) => expand_wildcard_name(name, subdomains)
+    }
+}
+
+fn get_base_domain(name: &str) -> Option<String> {
+    if let Some(regex) = name.strip_prefix('~') {
+        return get_regex_base_domain(regex);
+    }
+
+    let labels: Vec<&str> = name.split('.').collect();
+
+    let last_wildcard_label = labels.iter().rposition(|label| is_wildcard_name(label))?;
+    let base_labels = &labels[last_wildcard_label + 1..];
+
+    if base_labels.len() >= 2 {
+        Some(base_labels.join("."))
+
+    } else {
+        None
+    }
+}
+
+/// Literal domain labels at the end of regex: `^(?<sub>.+)\.example\.com$` -> `example.com`
+fn get_regex_base_domain(regex: &str) -> Option<String> {
+    let chars: Vec<char> = regex.strip_suffix('$').unwrap_or(regex).chars().collect();
+
+    let mut suffix: Vec<char> = Vec::new();
+    let mut position = chars.len();
+
+    while position > 0 {
+        let c = chars[position - 1];
+        let escaped = position >= 2 && chars[position - 2] == '\\';
+
+        if escaped && c == '.' {
+            suffix.push(c);
+            position -= 2;
+
+        } else if !escaped && (c.is_ascii_alphanumeric() || c == '-') {
+            suffix.push(c);
+            position -= 1;
+
+        } else {
+            break;
+        }
+    }
+
+    suffix.reverse();
+    let suffix: String = suffix.into_iter().collect();
+
+    // the first label is a part of subdomain unless the whole regex is literal
+    let whole_literal = position == 0 || (position == 1 && chars[0] == '^');
+
+    let base_domain = if whole_literal || suffix.starts_with('.') {
+        suffix.trim_start_matches('.')
+    } else {
+        suffix.split_once('.').map(|(_, rest)| rest)?
+    };
+
+    if base_domain.split('.').filter(|label| !label.is_empty()).count() >= 2 {
+        Some(base_domain.to_string())
+
+    } else {
+        None
+    }
+}
+
+fn expand_wildcard_name(name: &str, subdomains: &[String]) -> Vec<String> {
+    let (base_domain, pattern) = match (get_base_domain(name), get_name_pattern(name)) {
+        (Some(base_domain), Some(pattern)) => (base_domain, pattern),
+        _ => {
+            debug!("couldn't expand name '{name}'");
+            return Vec::new();
+        }
+    };
+
+    subdomains.iter()
+        .map(|subdomain| format!("{subdomain}.{base_domain}"))
+        .filter(|domain| pattern.is_match(domain))
+        .collect()
+}
+
+/// `*.example.com` -> `^.*\.example\.com$`, nginx regex names are used as is
+fn get_name_pattern(name: &str) -> Option<Regex> {
+    let pattern = match name.strip_prefix('~') {
+        Some(regex) => regex.to_string(),
+        None => format!("^{}$", regex::escape(name).replace("\\*", ".*").replace("\\?", "."))
+    };
+
+    match RegexBuilder::new(&pattern).case_insensitive(true).build() {
+        Ok(regex) => Some(regex),
+        Err(e) => {
+            warn!("unsupported name pattern '{name}': {}", e);
+            None
+        }
+    }
+}
+
+/// Wildcard name (`*.example.com`, `www.example.*`) or nginx regex name (`~^www\d+\.example\.com$`)
+fn is_wildcard_name(name: &str) -> bool {
+    name.starts_with('~') || name.contains(['*', '?'])
 }
 
-fn vhost_add_permitted(vhost: &VirtualHost, buffer: &Vec<VirtualHost>,
+fn vhost_add_permitted(vhost: &VirtualHost, buffer: &[VirtualHost],
                        include_custom_ports: bool) -> bool {
 
     let mut permitted = false;
@@ -63,12 +353,9 @@ fn vhost_add_permitted(vhost: &VirtualHost, buffer: &Vec<VirtualHost>,
             permitted = true;
         }
 
-    } else {
-        if vhost_has_standard_port(vhost.port) {
-            if !vec_contains_same_domain_with_port(buffer, &vhost.domain, vhost.port) {
-                permitted = true;
-            }
-        }
+    } else if vhost_has_standard_port(vhost.port) &&
+        !vec_contains_same_domain_with_port(buffer, &vhost.domain, vhost.port) {
+        permitted = true;
     }
 
     if permitted {
@@ -82,17 +369,19 @@ fn vhost_has_standard_port(port: i32) -> bool {
     port == DEFAULT_HTTP_PORT || port == DEFAULT_HTTPS_PORT
 }
 
-fn vec_contains_same_domain_with_port(vhosts: &Vec<VirtualHost>,
-                                      domain: &String, port: i32) -> bool {
+fn vec_contains_same_domain_with_port(vhosts: &[VirtualHost],
+                                      domain: &str, port: i32) -> bool {
     vhosts.iter()
-        .find(|vhost| &vhost.domain == domain && vhost.port == port).is_some()
+        .any(|vhost| vhost.domain == domain && vhost.port == port)
 }
 
 #[cfg(test)]
 mod filter_tests {
     use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
     use crate::domain::VirtualHost;
-    use crate::filter::{filter_by_domain_masks, filter_vhosts};
+    use crate::domain::Redirect;
+    use crate::filter::{DefaultServerPolicy, expand_aliases, filter_by_domain_masks, filter_redirects,
+                        filter_vhosts, resolve_default_servers, resolve_wildcard_names, WildcardPolicy};
 
     const DOMAIN: &str = "cronbox.ru";
     const DOMAIN2: &str = "tinyops.ru";
@@ -101,11 +390,19 @@ mod filter_tests {
 
     #[test]
     fn filter_by_domain_masks_should_exclude_domains_which_contain_at_least_one_mask() {
-        let vhost1 = VirtualHost { domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT };
-        let vhost2 = VirtualHost { domain: DOMAIN3.to_string(), port: DEFAULT_HTTPS_PORT };
-        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: 5384 };
-        let vhost4 = VirtualHost { domain: DOMAIN4.to_string(), port: DEFAULT_HTTPS_PORT };
-        let vhost5 = VirtualHost { domain: "localhost".to_string(), port: DEFAULT_HTTPS_PORT };
+        let vhost1 = VirtualHost {
+            domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT, ..Default::default()
+        };
+        let vhost2 = VirtualHost {
+            domain: DOMAIN3.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
+        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: 5384, ..Default::default() };
+        let vhost4 = VirtualHost {
+            domain: DOMAIN4.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
+        let vhost5 = VirtualHost {
+            domain: "localhost".to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
 
         let vhosts: Vec<VirtualHost> = vec![
             vhost1.clone(), vhost2.clone(), vhost3.clone(), vhost4.clone(), vhost5.clone()
@@ -118,16 +415,22 @@ mod filter_tests {
 
         assert_eq!(results.len(), 1);
 
-        let first_result = results.get(0).unwrap();
+        let first_result = results.first().unwrap();
         assert_eq!(first_result.domain, vhost3.domain);
     }
 
     #[test]
     fn filter_by_domain_masks_should_ignore_blank_masks() {
-        let vhost1 = VirtualHost { domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT };
-        let vhost2 = VirtualHost { domain: DOMAIN3.to_string(), port: DEFAULT_HTTPS_PORT };
-        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: 5384 };
-        let vhost4 = VirtualHost { domain: DOMAIN4.to_string(), port: DEFAULT_HTTPS_PORT };
+        let vhost1 = VirtualHost {
+            domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT, ..Default::default()
+        };
+        let vhost2 = VirtualHost {
+            domain: DOMAIN3.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
+        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: 5384, ..Default::default() };
+        let vhost4 = VirtualHost {
+            domain: DOMAIN4.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
 
         let vhosts: Vec<VirtualHost> = vec![
             vhost1.clone(), vhost2.clone(), vhost3.clone(), vhost4.clone()
@@ -142,9 +445,13 @@ mod filter_tests {
 
     #[test]
     fn result_without_custom_ports_should_contain_only_http_or_https_ports() {
-        let vhost1 = VirtualHost { domain: DOMAIN.to_string(), port: 7435 };
-        let vhost2 = VirtualHost { domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT };
-        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT };
+        let vhost1 = VirtualHost { domain: DOMAIN.to_string(), port: 7435, ..Default::default() };
+        let vhost2 = VirtualHost {
+            domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT, ..Default::default()
+        };
+        let vhost3 = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
 
         let vhosts: Vec<VirtualHost> = vec![vhost1.clone(), vhost2.clone(), vhost3.clone()];
 
@@ -167,9 +474,15 @@ mod filter_tests {
 
     #[test]
     fn result_should_not_contain_duplicates_without_custom_ports() {
-        let vhost1 = VirtualHost { domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT };
-        let vhost2 = VirtualHost { domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT };
-        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT };
+        let vhost1 = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
+        let vhost2 = VirtualHost {
+            domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT, ..Default::default()
+        };
+        let vhost3 = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
 
         let vhosts: Vec<VirtualHost> = vec![vhost1.clone(), vhost2.clone(), vhost3.clone()];
 
@@ -194,9 +507,15 @@ mod filter_tests {
     fn result_should_not_contain_duplicates_with_custom_ports() {
         let custom_port = 4113;
 
-        let vhost1 = VirtualHost { domain: DOMAIN.to_string(), port: custom_port };
-        let vhost2 = VirtualHost { domain: DOMAIN2.to_string(), port: DEFAULT_HTTPS_PORT };
-        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: custom_port };
+        let vhost1 = VirtualHost {
+            domain: DOMAIN.to_string(), port: custom_port, ..Default::default()
+        };
+        let vhost2 = VirtualHost {
+            domain: DOMAIN2.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
+        let vhost3 = VirtualHost {
+            domain: DOMAIN.to_string(), port: custom_port, ..Default::default()
+        };
 
         let vhosts: Vec<VirtualHost> = vec![vhost1.clone(), vhost2.clone(), vhost3.clone()];
 
@@ -216,4 +535,194 @@ mod filter_tests {
 
         assert!(vhost2_found.is_some());
     }
+
+    #[test]
+    fn expand_aliases_should_add_vhost_per_alias() {
+        let vhost1 = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTP_PORT,
+            aliases: vec![DOMAIN2.to_string(), DOMAIN3.to_string()], ..Default::default()
+        };
+        let vhost2 = VirtualHost {
+            domain: DOMAIN4.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
+
+        let results = expand_aliases(&[vhost1, vhost2], &WildcardPolicy::Skip);
+
+        assert_eq!(results.len(), 4);
+
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec![DOMAIN, DOMAIN2, DOMAIN3, DOMAIN4]);
+
+        assert!(results.iter().all(|vhost| vhost.aliases.is_empty()));
+        assert!(results.iter().take(3).all(|vhost| vhost.port == DEFAULT_HTTP_PORT));
+    }
+
+    #[test]
+    fn expand_aliases_should_skip_alias_served_by_another_vhost() {
+        let vhost1 = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTP_PORT,
+            aliases: vec![DOMAIN2.to_string(), DOMAIN3.to_string()], ..Default::default()
+        };
+        let vhost2 = VirtualHost {
+            domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT, ..Default::default()
+        };
+        let vhost3 = VirtualHost {
+            domain: DOMAIN4.to_string(), port: DEFAULT_HTTPS_PORT,
+            aliases: vec![DOMAIN3.to_string()], ..Default::default()
+        };
+
+        let results = expand_aliases(&[vhost1, vhost2, vhost3], &WildcardPolicy::Skip);
+
+        let domains: Vec<(&str, i32)> = results.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.port)).collect();
+
+        assert_eq!(domains, vec![
+            (DOMAIN, DEFAULT_HTTP_PORT), (DOMAIN3, DEFAULT_HTTP_PORT),
+            (DOMAIN2, DEFAULT_HTTP_PORT),
+            (DOMAIN4, DEFAULT_HTTPS_PORT), (DOMAIN3, DEFAULT_HTTPS_PORT)
+        ]);
+    }
+
+    #[test]
+    fn expand_aliases_should_skip_wildcard_aliases_with_skip_policy() {
+        let vhost = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT,
+            aliases: vec![format!("*.{DOMAIN2}"), format!("www.{DOMAIN2}")],
+            ..Default::default()
+        };
+
+        let results = expand_aliases(&[vhost], &WildcardPolicy::Skip);
+
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec![DOMAIN.to_string(), format!("www.{DOMAIN2}")]);
+    }
+
+    #[test]
+    fn expand_aliases_should_use_base_domain_for_wildcard_aliases_with_base_domain_policy() {
+        let vhost = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT,
+            aliases: vec![
+                format!("*.{DOMAIN2}"), format!("dev-*.{DOMAIN2}"), format!("*.{DOMAIN4}"),
+                "*.com".to_string(), "*".to_string()
+            ], ..Default::default()
+        };
+
+        let results = expand_aliases(&[vhost], &WildcardPolicy::BaseDomain);
+
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec![DOMAIN, DOMAIN2, DOMAIN4]);
+    }
+
+    #[test]
+    fn filter_redirects_should_split_sites_and_redirects() {
+        let site = VirtualHost { domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default() };
+
+        let redirect = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTP_PORT,
+            redirect: Some(Redirect {
+                status: 301, target: format!("https://{DOMAIN}/"), keeps_host: false
+            }),
+            ..Default::default()
+        };
+
+        let vhosts = vec![site, redirect];
+
+        let sites = filter_redirects(&vhosts, false);
+        assert_eq!(sites.len(), 1);
+        assert_eq!(sites[0].port, DEFAULT_HTTPS_PORT);
+
+        let redirects = filter_redirects(&vhosts, true);
+        assert_eq!(redirects.len(), 1);
+        assert_eq!(redirects[0].port, DEFAULT_HTTP_PORT);
+    }
+
+    #[test]
+    fn default_servers_should_be_skipped_by_default() {
+        let default_server = VirtualHost { port: DEFAULT_HTTP_PORT, default_server: true, ..Default::default() };
+        let vhost = VirtualHost { domain: DOMAIN.to_string(), port: DEFAULT_HTTP_PORT, ..Default::default() };
+
+        let results = resolve_default_servers(&[default_server, vhost], DefaultServerPolicy::Skip);
+
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec![DOMAIN]);
+    }
+
+    #[test]
+    fn default_servers_should_use_listen_address_with_ip_policy() {
+        let vhosts = vec![
+            VirtualHost {
+                port: DEFAULT_HTTP_PORT, address: Some("10.0.0.1".to_string()), default_server: true,
+                redirect: Some(Redirect { status: 301, target: "https://$host/".to_string(), keeps_host: true }),
+                ..Default::default()
+            },
+            VirtualHost {
+                port: DEFAULT_HTTPS_PORT, tls: true, address: Some("2001:db8::1".to_string()),
+                default_server: true, ..Default::default()
+            },
+        ];
+
+        let results = resolve_default_servers(&vhosts, DefaultServerPolicy::Ip);
+
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec!["10.0.0.1", "[2001:db8::1]"]);
+
+        assert_eq!(results[0].redirect.as_ref().unwrap().target, "https://10.0.0.1/");
+    }
+
+    #[test]
+    fn expand_aliases_should_use_known_subdomains_with_expand_policy() {
+        let vhost = VirtualHost {
+            domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT,
+            aliases: vec![
+                format!("*.{DOMAIN2}"), format!("dev-*.{DOMAIN2}"),
+                format!("~^(?<sub>api|shop)\\.{}$", regex::escape(DOMAIN4))
+            ], ..Default::default()
+        };
+
+        let policy = WildcardPolicy::Expand(vec!["www".to_string(), "api".to_string(), "dev-1".to_string()]);
+
+        let results = expand_aliases(&[vhost], &policy);
+
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec![
+            DOMAIN.to_string(), format!("www.{DOMAIN2}"), format!("api.{DOMAIN2}"),
+            format!("dev-1.{DOMAIN2}"), format!("api.{DOMAIN4}")
+        ]);
+    }
+
+    #[test]
+    fn wildcard_primary_names_should_be_resolved_by_policy() {
+        let vhosts = vec![
+            VirtualHost {
+                domain: format!("*.{DOMAIN}"), port: DEFAULT_HTTPS_PORT,
+                aliases: vec![DOMAIN2.to_string()], ..Default::default()
+            },
+            VirtualHost {
+                domain: "~^(?<sub>.+)\\.tinyops\\.ru$".to_string(), port: DEFAULT_HTTP_PORT,
+                ..Default::default()
+            },
+            VirtualHost { domain: "www.example.*".to_string(), port: DEFAULT_HTTP_PORT, ..Default::default() },
+        ];
+
+        let results = resolve_wildcard_names(&vhosts, &WildcardPolicy::Skip);
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec![DOMAIN2]);
+        assert!(results[0].aliases.is_empty());
+
+        let results = resolve_wildcard_names(&vhosts, &WildcardPolicy::BaseDomain);
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec![DOMAIN, DOMAIN2]);
+
+        let policy = WildcardPolicy::Expand(vec!["www".to_string(), "api".to_string()]);
+
+        let results = resolve_wildcard_names(&vhosts, &policy);
+        let domains: Vec<&str> = results.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec![
+            format!("www.{DOMAIN}"), format!("api.{DOMAIN}"),
+            format!("www.{DOMAIN2}"), format!("api.{DOMAIN2}")
+        ]);
+
+        assert_eq!(results[0].aliases, vec![DOMAIN2]);
+        assert!(results[1].aliases.is_empty());
+    }
 }
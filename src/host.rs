@@ -0,0 +1,49 @@
+use std::fs;
+use std::net::UdpSocket;
+use std::process::Command;
+
+const KERNEL_HOSTNAME_FILE: &str = "/proc/sys/kernel/hostname";
+
+/// Any routable address, packets aren't sent by `connect` for UDP socket
+const ROUTE_PROBE_ADDRESS: &str = "192.0.2.1:80";
+
+/// Host FQDN from `hostname -f`, kernel host name is used as fallback.
+pub fn get_host_fqdn() -> Option<String> {
+    let fqdn = Command::new("hostname").arg("-f").output().ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        .and_then(|output| get_host_name(&output));
+
+    fqdn.or_else(|| {
+        debug!("couldn't get host fqdn with 'hostname -f', use kernel host name");
+        fs::read_to_string(KERNEL_HOSTNAME_FILE).ok().and_then(|output| get_host_name(&output))
+    })
+}
+
+/// Source address for default route
+pub fn get_host_ip() -> Option<String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect(ROUTE_PROBE_ADDRESS).ok()?;
+
+    let ip = socket.local_addr().ok()?.ip();
+
+    if ip.is_unspecified() { None } else { Some(ip.to_string()) }
+}
+
+fn get_host_name(output: &str) -> Option<String> {
+    let name = output.trim().to_lowercase();
+
+    if name.is_empty() || name.contains(char::is_whitespace) { None } else { Some(name) }
+}
+
+#[cfg(test)]
+mod host_tests {
+    use crate::host::get_host_name;
+
+    #[test]
+    fn host_name_should_be_taken_from_command_output() {
+        assert_eq!(get_host_name("Web01.Example.com\n"), Some("web01.example.com".to_string()));
+        assert_eq!(get_host_name(" \n"), None);
+        assert_eq!(get_host_name("hostname: Name or service not known\n"), None);
+    }
+}
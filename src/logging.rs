@@ -8,14 +8,14 @@ const FILE_APPENDER_NAME: &str = "file";
 const LOG_FILE_PATH: &str = "/var/log/zabbix/vhdt.log";
 
 fn get_logging_level_from_string(level: &str) -> LevelFilter {
-    return match level {
+    match level {
         "debug" => LevelFilter::Debug,
         "error" => LevelFilter::Error,
         "warn" => LevelFilter::Warn,
         "trace" => LevelFilter::Trace,
         "off" => LevelFilter::Off,
         _ => LevelFilter::Info
-    };
+    }
 }
 
 pub fn get_logging_config(logging_level: &str) -> Config {
@@ -28,7 +28,7 @@ pub fn get_logging_config(logging_level: &str) -> Config {
             Root::builder()
                 .appender(FILE_APPENDER_NAME)
                 .build(level)
-        ).expect(&format!("couldn't create log file '{}'", LOG_FILE_PATH))
+        ).unwrap_or_else(|_| panic!("couldn't create log file '{}'", LOG_FILE_PATH))
 }
 
 fn get_file_appender_definition() -> Appender {
@@ -6,18 +6,24 @@ extern crate serde_json;
 use std::env;
 use std::path::Path;
 use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use clap::{App, Arg, ArgMatches};
+use serde::Serialize;
 use serde_json::json;
 
 use crate::apache::get_apache_discovery_config;
-use crate::cli::get_app_config;
-use crate::domain::{Site, VirtualHost};
-use crate::filter::{filter_by_domain_masks, filter_vhosts};
+use crate::cli::{AppConfig, DiscoveryType, get_app_config};
+use crate::backend::get_backend_endpoints;
+use crate::certificate::{check_certificate_names, get_certificate_files, read_certificates};
+use crate::domain::{BackendEndpoint, Site, Stream, VirtualHost};
+use crate::filter::{expand_aliases, filter_by_domain_masks, filter_redirects, filter_vhosts,
+                    resolve_default_servers, resolve_wildcard_names};
 use crate::logging::get_logging_config;
 use crate::nginx::get_nginx_discovery_config;
 use crate::site::get_domains_from_vhosts;
-use crate::webserver::get_vhosts;
+use crate::stream::get_streams_from_stream_servers;
+use crate::webserver::{get_from_config_files, get_vhosts};
 
 mod logging;
 
@@ -33,10 +39,20 @@ mod apache;
 
 mod site;
 
+mod stream;
+
+mod backend;
+
+mod certificate;
+
 mod filter;
 
 mod cli;
 
+mod host;
+
+mod annotation;
+
 #[cfg(test)]
 mod test_utils;
 
@@ -47,6 +63,33 @@ const DEFAULT_HTTPS_PORT: i32 = 443;
 const INCLUDE_DOMAINS_WITH_WWW: &str = "include-www";
 const INCLUDE_CUSTOM_PORTS_OPTION: &str = "include-custom-ports";
 
+const ALL_SERVER_NAMES_OPTION: &str = "all-server-names";
+const CHECK_CERTIFICATE_NAMES_OPTION: &str = "check-certificate-names";
+
+const WILDCARD_NAMES_ARGUMENT: &str = "wildcard-names";
+const WILDCARD_NAMES_SKIP_VALUE: &str = "skip";
+const WILDCARD_NAMES_BASE_DOMAIN_VALUE: &str = "base-domain";
+const WILDCARD_NAMES_EXPAND_VALUE: &str = "expand";
+
+const WILDCARD_SUBDOMAINS_ARGUMENT: &str = "wildcard-subdomains";
+
+const IDN_FORMAT_ARGUMENT: &str = "idn-format";
+const IDN_FORMAT_UNICODE_NAME_VALUE: &str = "unicode-name";
+const IDN_FORMAT_PUNYCODE_VALUE: &str = "punycode";
+const IDN_FORMAT_UNICODE_VALUE: &str = "unicode";
+
+const DEFAULT_SERVERS_ARGUMENT: &str = "default-servers";
+const DEFAULT_SERVERS_SKIP_VALUE: &str = "skip";
+const DEFAULT_SERVERS_HOSTNAME_VALUE: &str = "hostname";
+const DEFAULT_SERVERS_IP_VALUE: &str = "ip";
+
+const DISCOVERY_TYPE_ARGUMENT: &str = "discovery-type";
+const DISCOVERY_TYPE_VHOSTS_VALUE: &str = "vhosts";
+const DISCOVERY_TYPE_REDIRECTS_VALUE: &str = "redirects";
+const DISCOVERY_TYPE_STREAMS_VALUE: &str = "streams";
+const DISCOVERY_TYPE_BACKENDS_VALUE: &str = "backends";
+const DISCOVERY_TYPE_CERTIFICATES_VALUE: &str = "certificates";
+
 const DOMAIN_IGNORE_MASKS_OPTION: &str = "ignore-by-masks";
 
 const WWW_SEARCH_PATTERN: &str = "www.";
@@ -65,12 +108,16 @@ const VHOST_FILE_EXTENSIONS_DEFAULT_VALUE: &str = ".conf,.vhost";
 const NGINX_VHOSTS_PATH_ARGUMENT: &str = "nginx-vhosts-path";
 const NGINX_VHOSTS_PATH_SHORT_ARGUMENT: &str = "n";
 
+const NGINX_CONFIG_ARGUMENT: &str = "nginx-config";
+
 const RECURSIVE_OPTION: &str = "r";
 
 const FALL_ON_PARSE_ERROR_OPTION: &str = "fall-on-parse-error";
 const FALL_ON_PARSE_ERROR_SHORT_OPTION: &str = "f";
 
 const APACHE_VHOSTS_PATH_ARGUMENT: &str = "apache-vhosts-path";
+const APACHE_CONFIG_ARGUMENT: &str = "apache-config";
+const APACHE_MODULES_ARGUMENT: &str = "apache-modules";
 const APACHE_VHOSTS_PATH_SHORT_ARGUMENT: &str = "a";
 
 const USE_DATA_PROPERTY_ARGUMENT: &str = "use-data-property";
@@ -102,6 +149,70 @@ fn main() {
                 .long(INCLUDE_CUSTOM_PORTS_OPTION)
                 .help("include domains with custom ports")
         )
+        .arg(
+            Arg::with_name(ALL_SERVER_NAMES_OPTION)
+                .long(ALL_SERVER_NAMES_OPTION)
+                .help("add vhost for every name from nginx 'server_name' and \
+                        apache 'ServerAlias', not only the first one")
+        )
+        .arg(
+            Arg::with_name(CHECK_CERTIFICATE_NAMES_OPTION)
+                .long(CHECK_CERTIFICATE_NAMES_OPTION)
+                .help("log warning when vhost name isn't covered by its certificate SANs")
+        )
+        .arg(
+            Arg::with_name(WILDCARD_NAMES_ARGUMENT)
+                .long(WILDCARD_NAMES_ARGUMENT)
+                .help("how to handle names with wildcards (*.example.com) and nginx regex names. \
+                        skip - ignore them, base-domain - use domain after wildcard (example.com), \
+                        expand - use matching subdomains from --wildcard-subdomains")
+                .possible_values(&[WILDCARD_NAMES_SKIP_VALUE, WILDCARD_NAMES_BASE_DOMAIN_VALUE,
+                                   WILDCARD_NAMES_EXPAND_VALUE])
+                .default_value(WILDCARD_NAMES_SKIP_VALUE)
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(WILDCARD_SUBDOMAINS_ARGUMENT)
+                .long(WILDCARD_SUBDOMAINS_ARGUMENT)
+                .help("known subdomains for '--wildcard-names expand'. Use ',' char as value \
+                        separator. Example: www,api,shop")
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(IDN_FORMAT_ARGUMENT)
+                .long(IDN_FORMAT_ARGUMENT)
+                .help("forms of international domain names. unicode-name - unicode {#NAME} \
+                        and punycode {#URL}, punycode - punycode for both, unicode - unicode for both")
+                .possible_values(&[IDN_FORMAT_UNICODE_NAME_VALUE, IDN_FORMAT_PUNYCODE_VALUE,
+                                   IDN_FORMAT_UNICODE_VALUE])
+                .default_value(IDN_FORMAT_UNICODE_NAME_VALUE)
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(DEFAULT_SERVERS_ARGUMENT)
+                .long(DEFAULT_SERVERS_ARGUMENT)
+                .help("how to report nginx catch-all servers ('server_name _', 'default_server' \
+                        without names). skip - ignore them, hostname - use host fqdn, \
+                        ip - use listen address or host ip")
+                .possible_values(&[DEFAULT_SERVERS_SKIP_VALUE, DEFAULT_SERVERS_HOSTNAME_VALUE,
+                                   DEFAULT_SERVERS_IP_VALUE])
+                .default_value(DEFAULT_SERVERS_SKIP_VALUE)
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(DISCOVERY_TYPE_ARGUMENT)
+                .long(DISCOVERY_TYPE_ARGUMENT)
+                .help("what to discover. vhosts - sites, redirects - vhosts which redirect \
+                        to another url, with target url and expected status code, \
+                        streams - nginx stream (TCP/UDP) servers, \
+                        backends - nginx upstream servers and proxy_pass/fastcgi_pass addresses, \
+                        certificates - unique TLS certificate files of vhosts with expiry")
+                .possible_values(&[DISCOVERY_TYPE_VHOSTS_VALUE, DISCOVERY_TYPE_REDIRECTS_VALUE,
+                                   DISCOVERY_TYPE_STREAMS_VALUE, DISCOVERY_TYPE_BACKENDS_VALUE,
+                                   DISCOVERY_TYPE_CERTIFICATES_VALUE])
+                .default_value(DISCOVERY_TYPE_VHOSTS_VALUE)
+                .takes_value(true).required(false)
+        )
         .arg(
             Arg::with_name(VHOST_FILE_EXTENSIONS_ARGUMENT)
                 .long(VHOST_FILE_EXTENSIONS_ARGUMENT)
@@ -126,12 +237,36 @@ fn main() {
                 .long(NGINX_VHOSTS_PATH_ARGUMENT)
                 .takes_value(true).required(false)
         )
+        .arg(
+            Arg::with_name(NGINX_CONFIG_ARGUMENT)
+                .help("discover vhosts loaded by nginx starting from root config file \
+                        and following 'include' directives. example: /etc/nginx/nginx.conf")
+                .long(NGINX_CONFIG_ARGUMENT)
+                .takes_value(true).required(false)
+                .conflicts_with(NGINX_VHOSTS_PATH_ARGUMENT)
+        )
         .arg(
             Arg::with_name(APACHE_VHOSTS_PATH_ARGUMENT)
                 .short(APACHE_VHOSTS_PATH_SHORT_ARGUMENT)
                 .help("set apache vhosts root path")
                 .long(APACHE_VHOSTS_PATH_ARGUMENT)
                 .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(APACHE_CONFIG_ARGUMENT)
+                .help("discover vhosts loaded by apache starting from main config file \
+                        and following 'Include' directives. example: /etc/httpd/conf/httpd.conf")
+                .long(APACHE_CONFIG_ARGUMENT)
+                .takes_value(true).required(false)
+                .conflicts_with(APACHE_VHOSTS_PATH_ARGUMENT)
+        )
+        .arg(
+            Arg::with_name(APACHE_MODULES_ARGUMENT)
+                .help("set loaded apache modules for <IfModule> sections, by default modules \
+                        are taken from 'LoadModule' directives. Use ',' char as value separator. \
+                        Example: ssl_module,rewrite_module")
+                .long(APACHE_MODULES_ARGUMENT)
+                .takes_value(true).required(false)
         ).arg(
             Arg::with_name(DOMAIN_IGNORE_MASKS_OPTION)
                 .short(DOMAIN_IGNORE_MASKS_OPTION)
@@ -165,16 +300,24 @@ fn main() {
 
     info!("[~] collect virtual hosts..");
     info!("- include domains with custom ports: {}", &app_config.include_custom_domains);
-    let mut vhosts: Vec<VirtualHost> = Vec::new();
+    info!("- all server names: {}", &app_config.all_server_names);
+    info!("- discovery type: {:?}", &app_config.discovery_type);
 
-    let nginx_vhosts_path: &Path = get_nginx_vhosts_path(&matches);
-    debug!("- nginx vhosts root: '{}'", nginx_vhosts_path.display());
+    if app_config.discovery_type == DiscoveryType::Streams {
+        let streams = get_streams(&matches, &app_config);
+        print_low_level_discovery_json(&matches, streams);
+        return;
+    }
 
-    let nginx_discovery_config = get_nginx_discovery_config(
-        app_config.recursive_mode, &app_config.vhost_file_extensions);
+    if app_config.discovery_type == DiscoveryType::Backends {
+        let backends = get_backends(&matches, &app_config);
+        print_low_level_discovery_json(&matches, backends);
+        return;
+    }
+
+    let mut vhosts: Vec<VirtualHost> = Vec::new();
 
-    match get_vhosts(nginx_vhosts_path, &nginx_discovery_config,
-                     app_config.fall_on_parse_errors) {
+    match get_nginx_vhosts(&matches, &app_config) {
         Ok(mut nginx_vhosts) => {
             debug!("nginx vhosts collected:");
             debug!("{:?}", nginx_vhosts);
@@ -188,13 +331,26 @@ fn main() {
         }
     }
 
-    let apache_vhosts_path: &Path = get_apache_vhosts_path(&matches);
-    debug!("apache vhosts root: '{}'", apache_vhosts_path.display());
+    let apache_vhosts_result = match matches.value_of(APACHE_CONFIG_ARGUMENT) {
+        Some(apache_root_config) => {
+            debug!("- apache root config: '{apache_root_config}'");
+            apache::get_virtual_hosts_from_root_config(Path::new(apache_root_config),
+                                                      &app_config.apache_modules)
+        }
+        None => {
+            let apache_vhosts_path: &Path = get_apache_vhosts_path(&matches);
+            debug!("apache vhosts root: '{}'", apache_vhosts_path.display());
+
+            let apache_discovery_config = get_apache_discovery_config(
+                app_config.recursive_mode, &app_config.vhost_file_extensions,
+                &app_config.apache_modules);
 
-    let apache_discovery_config = get_apache_discovery_config(
-        app_config.recursive_mode, &app_config.vhost_file_extensions);
+            get_vhosts(apache_vhosts_path, &apache_discovery_config,
+                       app_config.fall_on_parse_errors)
+        }
+    };
 
-    match get_vhosts(apache_vhosts_path, &apache_discovery_config, app_config.fall_on_parse_errors) {
+    match apache_vhosts_result {
         Ok(mut apache_vhosts) => {
             debug!("apache vhosts collected:");
             debug!("{:?}", apache_vhosts);
@@ -208,21 +364,120 @@ fn main() {
         }
     }
 
-    let mut filtered_vhosts = filter_vhosts(&vhosts, app_config.include_custom_domains);
-    filtered_vhosts = filter_by_domain_masks(&filtered_vhosts, &app_config.domain_ignore_masks);
+    vhosts = resolve_default_servers(&vhosts, app_config.default_server_policy);
 
-    let sites: Vec<Site> = get_domains_from_vhosts(filtered_vhosts, app_config.include_domains_with_www);
+    if app_config.discovery_type == DiscoveryType::Certificates {
+        vhosts = filter_by_domain_masks(&vhosts, &app_config.domain_ignore_masks);
+        vhosts = read_certificates(&vhosts);
 
-    let json;
+        let certificate_files = get_certificate_files(&vhosts, get_unix_time());
+        print_low_level_discovery_json(&matches, certificate_files);
+        return;
+    }
 
-    if matches.is_present(USE_DATA_PROPERTY_ARGUMENT) {
-        json = get_low_level_discovery_json_with_data_property(sites);
+    let mut filtered_vhosts = get_site_vhosts(&vhosts, &app_config);
 
-    } else {
-        json = get_low_level_discovery_json(sites);
+    filtered_vhosts = read_certificates(&filtered_vhosts);
+
+    if app_config.check_certificate_names {
+        check_certificate_names(&filtered_vhosts);
+    }
+
+    let sites: Vec<Site> = get_domains_from_vhosts(
+        filtered_vhosts, app_config.include_domains_with_www, app_config.idn_format);
+
+    print_low_level_discovery_json(&matches, sites);
+}
+
+/// Vhosts for `vhosts` and `redirects` discovery. Redirects are filtered after names are resolved and
+/// aliases are expanded, every name is classified by its own response.
+fn get_site_vhosts(vhosts: &[VirtualHost], app_config: &AppConfig) -> Vec<VirtualHost> {
+    let mut vhosts = resolve_wildcard_names(vhosts, &app_config.wildcard_policy);
+
+    if app_config.all_server_names {
+        vhosts = expand_aliases(&vhosts, &app_config.wildcard_policy);
+    }
+
+    vhosts = filter_redirects(&vhosts, app_config.discovery_type == DiscoveryType::Redirects);
+
+    let filtered_vhosts = filter_vhosts(&vhosts, app_config.include_custom_domains);
+    filter_by_domain_masks(&filtered_vhosts, &app_config.domain_ignore_masks)
+}
+
+fn get_nginx_vhosts(matches: &ArgMatches, app_config: &AppConfig) -> anyhow::Result<Vec<VirtualHost>> {
+    match matches.value_of(NGINX_CONFIG_ARGUMENT) {
+        Some(nginx_root_config) => {
+            debug!("- nginx root config: '{nginx_root_config}'");
+            nginx::get_virtual_hosts_from_root_config(Path::new(nginx_root_config))
+        }
+        None => {
+            let nginx_vhosts_path: &Path = get_nginx_vhosts_path(matches);
+            debug!("- nginx vhosts root: '{}'", nginx_vhosts_path.display());
+
+            let nginx_discovery_config = get_nginx_discovery_config(
+                app_config.recursive_mode, &app_config.vhost_file_extensions);
+
+            get_vhosts(nginx_vhosts_path, &nginx_discovery_config,
+                       app_config.fall_on_parse_errors)
+        }
+    }
+}
+
+/// Backend endpoints of nginx vhosts, apache `ProxyPass` isn't supported
+fn get_backends(matches: &ArgMatches, app_config: &AppConfig) -> Vec<BackendEndpoint> {
+    match get_nginx_vhosts(matches, app_config) {
+        Ok(nginx_vhosts) => {
+            debug!("nginx vhosts collected:");
+            debug!("{:?}", nginx_vhosts);
+            get_backend_endpoints(&nginx_vhosts)
+        }
+        Err(e) => {
+            error!("{}", e);
+            if app_config.fall_on_parse_errors {
+                exit(EXIT_CODE_ERROR)
+            }
+            Vec::new()
+        }
+    }
+}
+
+/// nginx stream servers, apache doesn't have them
+fn get_streams(matches: &ArgMatches, app_config: &AppConfig) -> Vec<Stream> {
+    let stream_servers_result = match matches.value_of(NGINX_CONFIG_ARGUMENT) {
+        Some(nginx_root_config) => {
+            debug!("- nginx root config: '{nginx_root_config}'");
+            nginx::get_stream_servers_from_root_config(Path::new(nginx_root_config))
+        }
+        None => {
+            let nginx_vhosts_path: &Path = get_nginx_vhosts_path(matches);
+            debug!("- nginx vhosts root: '{}'", nginx_vhosts_path.display());
+
+            let nginx_discovery_config = get_nginx_discovery_config(
+                app_config.recursive_mode, &app_config.vhost_file_extensions);
+
+            get_from_config_files(nginx_vhosts_path, &nginx_discovery_config,
+                                  app_config.fall_on_parse_errors, nginx::get_stream_servers_from_file)
+        }
     };
 
-    println!("{json}");
+    match stream_servers_result {
+        Ok(stream_servers) => {
+            debug!("nginx stream servers collected:");
+            debug!("{:?}", stream_servers);
+            get_streams_from_stream_servers(&stream_servers)
+        }
+        Err(e) => {
+            error!("{}", e);
+            if app_config.fall_on_parse_errors {
+                exit(EXIT_CODE_ERROR)
+            }
+            Vec::new()
+        }
+    }
+}
+
+fn get_unix_time() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs() as i64).unwrap_or(0)
 }
 
 fn init_logging(matches: &ArgMatches) {
@@ -236,11 +491,11 @@ fn init_logging(matches: &ArgMatches) {
 
 fn init_working_dir(matches: &ArgMatches) {
     let working_directory: &Path = get_argument_path_value(
-        &matches, WORK_DIR_ARGUMENT, WORK_DIR_SHORT_ARGUMENT, WORKDIR);
+        matches, WORK_DIR_ARGUMENT, WORK_DIR_SHORT_ARGUMENT, WORKDIR);
 
     debug!("working directory '{}'", &working_directory.display());
 
-    env::set_current_dir(&working_directory).expect("couldn't set working directory");
+    env::set_current_dir(working_directory).expect("couldn't set working directory");
 }
 
 fn get_argument_path_value<'a>(matches: &'a ArgMatches, long_argument: &str,
@@ -260,36 +515,51 @@ fn get_argument_path_value<'a>(matches: &'a ArgMatches, long_argument: &str,
         }
     }
 
-    return path;
+    path
 }
 
 fn get_nginx_vhosts_path<'a>(matches: &'a ArgMatches) -> &'a Path {
-    get_argument_path_value(&matches, NGINX_VHOSTS_PATH_ARGUMENT,
+    get_argument_path_value(matches, NGINX_VHOSTS_PATH_ARGUMENT,
         NGINX_VHOSTS_PATH_SHORT_ARGUMENT, NGINX_VHOSTS_PATH)
 }
 
 fn get_apache_vhosts_path<'a>(matches: &'a ArgMatches) -> &'a Path {
-    get_argument_path_value(&matches, APACHE_VHOSTS_PATH_ARGUMENT,
+    get_argument_path_value(matches, APACHE_VHOSTS_PATH_ARGUMENT,
                             APACHE_VHOSTS_PATH_SHORT_ARGUMENT, APACHE_VHOSTS_PATH)
 }
 
-fn get_low_level_discovery_json(sites: Vec<Site>) -> String {
-    let json_structure = json!(sites);
-    let json = serde_json::to_string(&json_structure).unwrap();
-    return json;
+fn print_low_level_discovery_json<T: Serialize>(matches: &ArgMatches, items: Vec<T>) {
+    let json = if matches.is_present(USE_DATA_PROPERTY_ARGUMENT) {
+        get_low_level_discovery_json_with_data_property(items)
+
+    } else {
+        get_low_level_discovery_json(items)
+    };
+
+    println!("{json}");
+}
+
+fn get_low_level_discovery_json<T: Serialize>(items: Vec<T>) -> String {
+    let json_structure = json!(items);
+    serde_json::to_string(&json_structure).unwrap()
 }
 
-fn get_low_level_discovery_json_with_data_property(sites: Vec<Site>) -> String {
-    let json_structure = json!({"data": sites});
-    let json = serde_json::to_string(&json_structure).unwrap();
-    return json;
+fn get_low_level_discovery_json_with_data_property<T: Serialize>(items: Vec<T>) -> String {
+    let json_structure = json!({"data": items});
+    serde_json::to_string(&json_structure).unwrap()
 }
 
 #[cfg(test)]
 mod main_tests {
-    use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT, get_low_level_discovery_json, get_low_level_discovery_json_with_data_property};
-    use crate::domain::{Site, VirtualHost};
-    use crate::site::{get_domains_from_vhosts, get_url};
+    use std::path::Path;
+
+    use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT, get_low_level_discovery_json, get_low_level_discovery_json_with_data_property,
+                get_site_vhosts};
+    use crate::cli::{AppConfig, DiscoveryType};
+    use crate::filter::{DefaultServerPolicy, WildcardPolicy};
+    use crate::nginx::get_virtual_hosts_from_file;
+    use crate::domain::{BackendEndpoint, Certificate, CertificateFile, Redirect, Site, Stream, VirtualHost};
+    use crate::site::{get_domains_from_vhosts, get_url, IdnFormat};
 
     const CUSTOM_VHOST_PORT: i32 = 5382;
 
@@ -298,7 +568,7 @@ mod main_tests {
         let domain = "quarkoman.com";
         let expected_url = format!("https://{}", domain);
 
-        assert_eq!(get_url(domain, DEFAULT_HTTPS_PORT), expected_url)
+        assert_eq!(get_url(domain, DEFAULT_HTTPS_PORT, false), expected_url)
     }
 
     #[test]
@@ -306,7 +576,7 @@ mod main_tests {
         let domain = "quarkoman.com";
         let expected_url = format!("http://{}", domain);
 
-        assert_eq!(get_url(domain, DEFAULT_HTTP_PORT), expected_url)
+        assert_eq!(get_url(domain, DEFAULT_HTTP_PORT, false), expected_url)
     }
 
     #[test]
@@ -314,7 +584,15 @@ mod main_tests {
         let domain = "quarkoman.com";
         let expected_url = format!("http://{}:{}", domain, CUSTOM_VHOST_PORT);
 
-        assert_eq!(get_url(domain, CUSTOM_VHOST_PORT), expected_url)
+        assert_eq!(get_url(domain, CUSTOM_VHOST_PORT, false), expected_url)
+    }
+
+    #[test]
+    fn get_url_should_return_url_with_https_and_port_for_tls_on_custom_port() {
+        let domain = "quarkoman.com";
+        let expected_url = format!("https://{}:{}", domain, CUSTOM_VHOST_PORT);
+
+        assert_eq!(get_url(domain, CUSTOM_VHOST_PORT, true), expected_url)
     }
 
     #[test]
@@ -325,12 +603,13 @@ mod main_tests {
 
         let vhost = VirtualHost {
             domain: String::from(&domain),
-            port: DEFAULT_HTTPS_PORT
+            port: DEFAULT_HTTPS_PORT,
+            ..Default::default()
         };
 
         vhosts.push(vhost);
 
-        let sites: Vec<Site> = get_domains_from_vhosts(vhosts, true);
+        let sites: Vec<Site> = get_domains_from_vhosts(vhosts, true, IdnFormat::UnicodeName);
 
         let expected_json: &str = r#"[{"{#NAME}":"meduttio.uk","{#URL}":"https://meduttio.uk"}]"#;
 
@@ -339,6 +618,125 @@ mod main_tests {
         assert_eq!(json, expected_json);
     }
 
+    #[test]
+    fn get_low_level_discovery_json_should_contain_bind_address_if_specified() {
+        let vhost = VirtualHost {
+            domain: String::from("ipv6.example.com"),
+            port: DEFAULT_HTTPS_PORT,
+            address: Some(String::from("2001:db8::1")),
+            ..Default::default()
+        };
+
+        let sites: Vec<Site> = get_domains_from_vhosts(vec![vhost], true, IdnFormat::UnicodeName);
+
+        let expected_json: &str = r#"[{"{#ADDRESS}":"2001:db8::1","{#NAME}":"ipv6.example.com","{#URL}":"https://ipv6.example.com"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(sites), expected_json);
+    }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_redirect_target_and_status() {
+        let vhost = VirtualHost {
+            domain: String::from("old.example.com"),
+            port: DEFAULT_HTTP_PORT,
+            redirect: Some(Redirect {
+                status: 301, target: String::from("https://example.com/"), keeps_host: false
+            }),
+            ..Default::default()
+        };
+
+        let sites: Vec<Site> = get_domains_from_vhosts(vec![vhost], true, IdnFormat::UnicodeName);
+
+        let expected_json: &str = r#"[{"{#EXPECTED_STATUS}":301,"{#NAME}":"old.example.com_http","{#REDIRECT_TO}":"https://example.com/","{#URL}":"http://old.example.com"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(sites), expected_json);
+    }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_certificate_macros() {
+        let vhost = VirtualHost {
+            domain: String::from("example.com"), port: DEFAULT_HTTPS_PORT,
+            certificate: Some(Certificate {
+                path: String::from("/etc/ssl/example.com.pem"), not_after: Some(2051222400),
+                sans: vec![String::from("example.com"), String::from("*.example.com")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let sites: Vec<Site> = get_domains_from_vhosts(vec![vhost], true, IdnFormat::UnicodeName);
+
+        let expected_json: &str = r#"[{"{#CERT_NOT_AFTER}":2051222400,"{#CERT_PATH}":"/etc/ssl/example.com.pem","{#CERT_SANS}":"example.com,*.example.com","{#NAME}":"example.com","{#URL}":"https://example.com"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(sites), expected_json);
+    }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_certificate_file_macros() {
+        let certificate_file = CertificateFile {
+            path: String::from("/etc/ssl/example.com.pem"), subject: String::from("CN=example.com"),
+            issuer: String::from("CN=R3,O=Let's Encrypt,C=US"), sans: String::from("example.com"),
+            not_after: 2051222400, days_left: 30, names: String::from("example.com")
+        };
+
+        let expected_json: &str = r#"[{"{#CERT_DAYS_LEFT}":30,"{#CERT_ISSUER}":"CN=R3,O=Let's Encrypt,C=US","{#CERT_NOT_AFTER}":2051222400,"{#CERT_PATH}":"/etc/ssl/example.com.pem","{#CERT_SANS}":"example.com","{#CERT_SUBJECT}":"CN=example.com","{#NAMES}":"example.com"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(vec![certificate_file]), expected_json);
+    }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_path() {
+        let vhost = VirtualHost {
+            domain: String::from("example.com"), port: DEFAULT_HTTPS_PORT,
+            paths: vec![String::from("/healthz")], ..Default::default()
+        };
+
+        let sites: Vec<Site> = get_domains_from_vhosts(vec![vhost], true, IdnFormat::UnicodeName);
+
+        let expected_json: &str = r#"[{"{#NAME}":"example.com/healthz","{#PATH}":"/healthz","{#URL}":"https://example.com/healthz"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(sites), expected_json);
+    }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_annotation_macros() {
+        let vhost = VirtualHost {
+            domain: String::from("billing.example.com"), port: DEFAULT_HTTPS_PORT,
+            display_name: Some(String::from("Billing API")), expected_status: Some(401),
+            tags: vec![String::from("team:payments"), String::from("env:prod")],
+            ..Default::default()
+        };
+
+        let sites: Vec<Site> = get_domains_from_vhosts(vec![vhost], true, IdnFormat::UnicodeName);
+
+        let expected_json: &str = r#"[{"{#DISPLAY_NAME}":"Billing API","{#EXPECTED_STATUS}":401,"{#NAME}":"billing.example.com","{#TAGS}":"team:payments,env:prod","{#URL}":"https://billing.example.com"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(sites), expected_json);
+    }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_stream_port_proto_and_upstream() {
+        let stream = Stream {
+            port: 5432, proto: String::from("tcp"), address: None, upstream: Some(String::from("db"))
+        };
+
+        let expected_json: &str = r#"[{"{#PORT}":5432,"{#PROTO}":"tcp","{#UPSTREAM}":"db"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(vec![stream]), expected_json);
+    }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_backend_host_and_port() {
+        let endpoint = BackendEndpoint {
+            backend: String::from("10.0.0.1:8080"), proto: String::from("http"),
+            host: Some(String::from("10.0.0.1")), port: Some(8080), upstream: Some(String::from("app"))
+        };
+
+        let expected_json: &str = r#"[{"{#BACKEND}":"10.0.0.1:8080","{#HOST}":"10.0.0.1","{#PORT}":8080,"{#PROTO}":"http","{#UPSTREAM}":"app"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(vec![endpoint]), expected_json);
+    }
+
     #[test]
     fn get_sites_vector_from_vhosts_should_return_domains_with_www_if_option_is_true() {
         let mut vhosts: Vec<VirtualHost> = Vec::new();
@@ -347,20 +745,22 @@ mod main_tests {
 
         let vhost1 = VirtualHost {
             domain: String::from(&domain1),
-            port: DEFAULT_HTTPS_PORT
+            port: DEFAULT_HTTPS_PORT,
+            ..Default::default()
         };
 
         let domain2 = String::from("www.meduttio.uk");
 
         let vhost2 = VirtualHost {
             domain: String::from(&domain2),
-            port: DEFAULT_HTTP_PORT
+            port: DEFAULT_HTTP_PORT,
+            ..Default::default()
         };
 
         vhosts.push(vhost1);
         vhosts.push(vhost2);
 
-        let sites: Vec<Site> = get_domains_from_vhosts(vhosts, true);
+        let sites: Vec<Site> = get_domains_from_vhosts(vhosts, true, IdnFormat::UnicodeName);
 
         assert_eq!(2, sites.len());
 
@@ -379,20 +779,22 @@ mod main_tests {
 
         let vhost1 = VirtualHost {
             domain: String::from(&domain1),
-            port: DEFAULT_HTTPS_PORT
+            port: DEFAULT_HTTPS_PORT,
+            ..Default::default()
         };
 
         let domain2 = String::from("www.meduttio.uk");
 
         let vhost2 = VirtualHost {
             domain: String::from(&domain2),
-            port: DEFAULT_HTTP_PORT
+            port: DEFAULT_HTTP_PORT,
+            ..Default::default()
         };
 
         vhosts.push(vhost1);
         vhosts.push(vhost2);
 
-        let sites: Vec<Site> = get_domains_from_vhosts(vhosts, false);
+        let sites: Vec<Site> = get_domains_from_vhosts(vhosts, false, IdnFormat::UnicodeName);
 
         assert_eq!(1, sites.len());
 
@@ -408,12 +810,13 @@ mod main_tests {
 
         let vhost = VirtualHost {
             domain: String::from(&domain),
-            port: DEFAULT_HTTPS_PORT
+            port: DEFAULT_HTTPS_PORT,
+            ..Default::default()
         };
 
         vhosts.push(vhost);
 
-        let sites: Vec<Site> = get_domains_from_vhosts(vhosts, true);
+        let sites: Vec<Site> = get_domains_from_vhosts(vhosts, true, IdnFormat::UnicodeName);
 
         let expected_json: &str =
             r#"{"data":[{"{#NAME}":"meduttio.uk","{#URL}":"https://meduttio.uk"}]}"#;
@@ -422,4 +825,32 @@ mod main_tests {
 
         assert_eq!(json, expected_json);
     }
+
+    fn get_site_vhosts_app_config(discovery_type: DiscoveryType) -> AppConfig {
+        AppConfig {
+            fall_on_parse_errors: false, include_domains_with_www: true, include_custom_domains: false,
+            recursive_mode: false, all_server_names: true, check_certificate_names: false,
+            wildcard_policy: WildcardPolicy::Skip, default_server_policy: DefaultServerPolicy::Skip,
+            idn_format: IdnFormat::UnicodeName, discovery_type, domain_ignore_masks: Vec::new(),
+            apache_modules: Vec::new(), vhost_file_extensions: Vec::new()
+        }
+    }
+
+    #[test]
+    fn expanded_aliases_should_be_classified_by_their_own_redirect() {
+        let vhosts = get_virtual_hosts_from_file(Path::new("test-data/nginx-vhosts/alias-redirect.conf")).unwrap();
+
+        let sites = get_site_vhosts(&vhosts, &get_site_vhosts_app_config(DiscoveryType::Vhosts));
+        let site_values: Vec<(&str, Option<u16>)> = sites.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.expected_status)).collect();
+
+        assert_eq!(site_values, vec![("example.com", Some(401))]);
+
+        let redirects = get_site_vhosts(&vhosts, &get_site_vhosts_app_config(DiscoveryType::Redirects));
+        let redirect_values: Vec<(&str, Option<&str>)> = redirects.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.redirect.as_ref().map(|redirect| redirect.target.as_str())))
+            .collect();
+
+        assert_eq!(redirect_values, vec![("www.example.com", Some("https://example.com/"))]);
+    }
 }
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use crate::domain::Backend;
+use crate::nginx::{HTTP_DIRECTIVE, SERVER_DIRECTIVE};
+use crate::nginx::parser::Directive;
+
+const UPSTREAM_DIRECTIVE: &str = "upstream";
+
+/// Directives with backend address and default protocol
+const PASS_DIRECTIVES: [(&str, &str); 4] = [
+    ("proxy_pass", "http"), ("fastcgi_pass", "fastcgi"), ("uwsgi_pass", "uwsgi"), ("grpc_pass", "grpc")
+];
+
+const UNIX_SOCKET_PREFIX: &str = "unix:";
+
+/// nginx uses 80 for upstream `server` without port whatever `proxy_pass` scheme is
+const UPSTREAM_SERVER_DEFAULT_PORT: u16 = 80;
+
+/// Upstream name -> server addresses
+pub type Upstreams = HashMap<String, Vec<String>>;
+
+/// Returns `upstream` blocks from top level and from `http` blocks.
+pub fn get_upstreams(directives: &[Directive]) -> Upstreams {
+    let mut upstreams = Upstreams::new();
+
+    for directive in directives {
+        if directive.name == UPSTREAM_DIRECTIVE && directive.is_block() {
+            let name = match directive.args.first() {
+                Some(name) => name,
+                None => continue
+            };
+
+            let servers: Vec<String> = directive.children().iter()
+                .filter(|child| child.name == SERVER_DIRECTIVE && !child.is_block())
+                .filter_map(|child| child.args.first())
+                .map(|address| address.to_string())
+                .collect();
+
+            debug!("upstream '{name}' found with servers {:?}", servers);
+
+            upstreams.insert(name.to_string(), servers);
+
+        } else if directive.name == HTTP_DIRECTIVE {
+            upstreams.extend(get_upstreams(directive.children()));
+        }
+    }
+
+    upstreams
+}
+
+/// Backends from `proxy_pass`, `fastcgi_pass`, `uwsgi_pass` and `grpc_pass` of the server block
+/// (including `location` and `if` blocks). Upstream names are replaced with upstream servers.
+pub fn get_backends(server: &Directive, upstreams: &Upstreams) -> Vec<Backend> {
+    let mut backends: Vec<Backend> = Vec::new();
+
+    for directive in server.children() {
+        if directive.is_block() {
+            for backend in get_backends(directive, upstreams) {
+                push_unique(&mut backends, backend);
+            }
+            continue;
+        }
+
+        let default_proto = match PASS_DIRECTIVES.iter().find(|(name, _)| *name == directive.name) {
+            Some((_, proto)) => proto,
+            None => continue
+        };
+
+        let target = match directive.args.first() {
+            Some(target) => target,
+            None => continue
+        };
+
+        if target.contains('$') {
+            debug!("backend with variables '{target}' at line {} isn't supported, skip", directive.line);
+            continue;
+        }
+
+        for backend in get_target_backends(target, default_proto, upstreams) {
+            push_unique(&mut backends, backend);
+        }
+    }
+
+    backends
+}
+
+/// `http://backend/api/`, `https://10.0.0.1:8443`, `127.0.0.1:9000`, `unix:/run/php/php-fpm.sock`,
+/// `grpc://[::1]:50051`, `http://unix:/tmp/backend.sock:/uri/`
+fn get_target_backends(target: &str, default_proto: &str, upstreams: &Upstreams) -> Vec<Backend> {
+    let (proto, address) = match target.split_once("://") {
+        Some((scheme, address)) => (scheme, address),
+        None => (default_proto, target)
+    };
+
+    if let Some(socket) = address.strip_prefix(UNIX_SOCKET_PREFIX) {
+        // proxy_pass http://unix:/path:/uri
+        let socket = socket.split(":/").next().unwrap_or(socket);
+        let socket = socket.strip_suffix(':').unwrap_or(socket);
+
+        return vec![Backend {
+            proto: proto.to_string(), address: format!("{UNIX_SOCKET_PREFIX}{socket}"), upstream: None
+        }];
+    }
+
+    let host = address.split('/').next().unwrap_or(address);
+
+    match upstreams.get(host) {
+        Some(servers) => servers.iter()
+            .map(|server| Backend {
+                proto: proto.to_string(),
+                address: get_address_with_port(server, Some(UPSTREAM_SERVER_DEFAULT_PORT)),
+                upstream: Some(host.to_string())
+            })
+            .collect(),
+
+        None => vec![Backend {
+            proto: proto.to_string(), address: get_address_with_port(host, get_default_port(proto)), upstream: None
+        }]
+    }
+}
+
+/// Adds default port if address doesn't have it
+fn get_address_with_port(address: &str, default_port: Option<u16>) -> String {
+    let has_port = match address.rsplit_once(':') {
+        Some((host, port)) => port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']')),
+        None => false
+    };
+
+    if has_port || address.starts_with(UNIX_SOCKET_PREFIX) {
+        return address.to_string();
+    }
+
+    match default_port {
+        Some(port) => format!("{address}:{port}"),
+        None => address.to_string()
+    }
+}
+
+/// nginx uses 80 for `http` and 443 for `https` if port of `proxy_pass` address is omitted
+fn get_default_port(proto: &str) -> Option<u16> {
+    match proto {
+        "http" | "grpc" => Some(80),
+        "https" | "grpcs" => Some(443),
+        _ => None
+    }
+}
+
+fn push_unique(backends: &mut Vec<Backend>, backend: Backend) {
+    if !backends.contains(&backend) {
+        backends.push(backend);
+    }
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use crate::nginx::backend::{get_backends, get_upstreams};
+    use crate::nginx::parser::parse_config;
+
+    fn get_backend_values(input: &str) -> Vec<(String, String, Option<String>)> {
+        let directives = parse_config(input).unwrap();
+        let upstreams = get_upstreams(&directives);
+
+        let server = directives.iter().find(|directive| directive.name == "server").unwrap();
+
+        get_backends(server, &upstreams).into_iter()
+            .map(|backend| (backend.proto, backend.address, backend.upstream))
+            .collect()
+    }
+
+    fn backend(proto: &str, address: &str, upstream: Option<&str>) -> (String, String, Option<String>) {
+        (proto.to_string(), address.to_string(), upstream.map(|upstream| upstream.to_string()))
+    }
+
+    #[test]
+    fn pass_directives_should_be_collected_from_locations() {
+        let backends = get_backend_values(
+            "server {\n    location / {\n        proxy_pass http://127.0.0.1:8080/;\n    }\n\
+             location /secure/ {\n        proxy_pass https://secure.example.com;\n    }\n\
+             location ~ \\.php$ {\n        fastcgi_pass unix:/run/php/php-fpm.sock;\n    }\n\
+             location /uwsgi {\n        if ($request_method = POST) {\n            \
+             uwsgi_pass 127.0.0.1:3031;\n        }\n    }\n\
+             location /grpc {\n        grpc_pass grpc://[::1]:50051;\n    }\n\
+             location /socket {\n        proxy_pass http://unix:/tmp/backend.sock:/api/;\n    }\n\
+             location /dynamic {\n        proxy_pass http://$backend;\n    }\n}");
+
+        assert_eq!(backends, vec![
+            backend("http", "127.0.0.1:8080", None),
+            backend("https", "secure.example.com:443", None),
+            backend("fastcgi", "unix:/run/php/php-fpm.sock", None),
+            backend("uwsgi", "127.0.0.1:3031", None),
+            backend("grpc", "[::1]:50051", None),
+            backend("http", "unix:/tmp/backend.sock", None),
+        ]);
+    }
+
+    #[test]
+    fn upstream_servers_without_port_should_use_port_80() {
+        let backends = get_backend_values(
+            "upstream app {\n    server app1.local;\n    server app2.local:8443;\n}\n\
+             server {\n    location / {\n        proxy_pass https://app;\n    }\n\
+             location /direct/ {\n        proxy_pass https://direct.local;\n    }\n}");
+
+        assert_eq!(backends, vec![
+            backend("https", "app1.local:80", Some("app")),
+            backend("https", "app2.local:8443", Some("app")),
+            backend("https", "direct.local:443", None),
+        ]);
+    }
+
+    #[test]
+    fn upstream_names_should_be_replaced_with_servers() {
+        let backends = get_backend_values(
+            "http {\n    upstream app {\n        server 10.0.0.1:8080 weight=2;\n        \
+             server app2.local;\n        keepalive 16;\n    }\n\
+             upstream php {\n        server unix:/run/php.sock;\n    }\n}\n\
+             server {\n    location / {\n        proxy_pass http://app;\n    }\n\
+             location /api/ {\n        proxy_pass http://app/api/;\n    }\n\
+             location ~ \\.php$ {\n        fastcgi_pass php;\n    }\n}");
+
+        assert_eq!(backends, vec![
+            backend("http", "10.0.0.1:8080", Some("app")),
+            backend("http", "app2.local:80", Some("app")),
+            backend("fastcgi", "unix:/run/php.sock", Some("php")),
+        ]);
+    }
+}
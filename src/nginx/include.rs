@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use glob::glob;
+
+use crate::nginx::parser::{Directive, parse_config_file};
+
+const INCLUDE_DIRECTIVE: &str = "include";
+
+/// Loads config starting from the root file (usually `/etc/nginx/nginx.conf`) and replaces
+/// `include` directives with directives from included files, like nginx does.
+///
+/// Relative include paths are resolved from the root config directory (nginx prefix).
+pub fn load_config_with_includes(root_config: &Path) -> anyhow::Result<Vec<Directive>> {
+    let prefix = root_config.parent().unwrap_or(Path::new("")).to_path_buf();
+    let mut loading_files: Vec<PathBuf> = Vec::new();
+    load_config_file(root_config, &prefix, &mut loading_files)
+}
+
+fn load_config_file(config_file: &Path, prefix: &Path,
+                    loading_files: &mut Vec<PathBuf>) -> anyhow::Result<Vec<Directive>> {
+    let canonical_path = config_file.canonicalize()
+                                    .unwrap_or_else(|_| config_file.to_path_buf());
+
+    if loading_files.contains(&canonical_path) {
+        return Err(anyhow!("include cycle detected for file '{}'", config_file.display()));
+    }
+
+    debug!("load config file '{}'", config_file.display());
+
+    loading_files.push(canonical_path);
+
+    let result = parse_config_file(config_file)
+        .and_then(|directives| resolve_includes(directives, prefix, loading_files));
+
+    loading_files.pop();
+
+    result
+}
+
+fn resolve_includes(directives: Vec<Directive>, prefix: &Path,
+                    loading_files: &mut Vec<PathBuf>) -> anyhow::Result<Vec<Directive>> {
+    let mut results: Vec<Directive> = Vec::new();
+
+    for mut directive in directives {
+        if directive.name == INCLUDE_DIRECTIVE && !directive.is_block() {
+            let include_path = directive.args.first().ok_or_else(
+                || anyhow!("include without path at line {}", directive.line))?;
+
+            for included_file in get_included_files(include_path, prefix)? {
+                let mut included_directives = load_config_file(
+                    &included_file, prefix, loading_files)?;
+                results.append(&mut included_directives);
+            }
+
+        } else {
+            if let Some(children) = directive.block.take() {
+                directive.block = Some(resolve_includes(children, prefix, loading_files)?);
+            }
+
+            results.push(directive);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Glob patterns may match nothing, plain paths must exist (same as nginx).
+fn get_included_files(include_path: &str, prefix: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let path = prefix.join(include_path);
+
+    if !is_glob_pattern(include_path) {
+        return Ok(vec![path]);
+    }
+
+    let path_pattern = path.to_str().ok_or_else(
+        || anyhow!("unsupported include path '{}'", path.display()))?;
+
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for entry in glob(path_pattern).context("invalid include pattern")? {
+        let file = entry?;
+
+        if file.is_file() {
+            files.push(file);
+        }
+    }
+
+    debug!("files included by pattern '{path_pattern}': {:?}", files);
+
+    Ok(files)
+}
+
+fn is_glob_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+#[cfg(test)]
+mod include_tests {
+    use std::path::Path;
+
+    use crate::nginx::include::load_config_with_includes;
+
+    #[test]
+    fn included_files_should_be_inserted_in_place_of_include_directive() {
+        let root_config = Path::new("test-data/nginx-root/nginx.conf");
+
+        let directives = load_config_with_includes(root_config).unwrap();
+
+        let http = directives.iter().find(|directive| directive.name == "http").unwrap();
+
+        assert!(http.children().iter().any(|directive| directive.name == "types"));
+        assert!(http.children().iter().all(|directive| directive.name != "include"));
+
+        let servers = http.children().iter()
+            .filter(|directive| directive.name == "server").count();
+        assert_eq!(servers, 3);
+    }
+
+    #[test]
+    fn return_error_for_missing_included_file() {
+        let root_config = Path::new("test-data/nginx-root/missing-include.conf");
+        assert!(load_config_with_includes(root_config).is_err());
+    }
+
+    #[test]
+    fn return_error_for_include_cycle() {
+        let root_config = Path::new("test-data/nginx-root/cycle.conf");
+        assert!(load_config_with_includes(root_config).is_err());
+    }
+}
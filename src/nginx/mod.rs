@@ -0,0 +1,881 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::annotation::{ANNOTATION_DIRECTIVE, Annotations, get_annotations};
+use crate::domain::{Certificate, NameResponse, StreamServer, VirtualHost};
+use crate::nginx::backend::{get_backends, get_upstreams, Upstreams};
+use crate::nginx::include::load_config_with_includes;
+use crate::nginx::parser::{Directive, parse_config_file};
+use crate::nginx::redirect::get_redirect;
+use crate::nginx::status::get_expected_status;
+use crate::nginx::stream::{get_stream_servers, is_stream_server};
+use crate::vhost::{VhostDiscoveryConfig, WebServer};
+use crate::webserver::{is_valid_domain_name, ListenAddress, normalize_domain_name, parse_listen_address};
+
+pub mod parser;
+
+pub mod include;
+
+pub mod redirect;
+
+pub mod backend;
+
+pub mod status;
+
+pub mod stream;
+
+const HTTP_DIRECTIVE: &str = "http";
+const SERVER_DIRECTIVE: &str = "server";
+const LISTEN_DIRECTIVE: &str = "listen";
+const SERVER_NAME_DIRECTIVE: &str = "server_name";
+const SSL_DIRECTIVE: &str = "ssl";
+const SSL_CERTIFICATE_DIRECTIVE: &str = "ssl_certificate";
+const LOCATION_DIRECTIVE: &str = "location";
+
+/// Invalid name which is used for catch-all servers by convention
+const CATCH_ALL_SERVER_NAME: &str = "_";
+
+const SSL_LISTEN_PARAMETER: &str = "ssl";
+const UDP_LISTEN_PARAMETER: &str = "udp";
+const DEFAULT_SERVER_LISTEN_PARAMETERS: [&str; 2] = ["default_server", "default"];
+
+const DEFAULT_LISTEN_PORT: i32 = 80;
+
+const HEALTH_CHECK_PATH_SEGMENTS: [&str; 8] = [
+    "health", "healthz", "healthcheck", "livez", "readyz", "ready", "ping", "status"
+];
+
+pub fn get_nginx_discovery_config(include_subdirs: bool,
+                                  file_extensions: &[String]) -> VhostDiscoveryConfig {
+    VhostDiscoveryConfig {
+        web_server: WebServer::Nginx,
+        include_subdirs,
+        file_extensions: file_extensions.to_vec(),
+        modules: Vec::new()
+    }
+}
+
+pub fn get_virtual_hosts_from_file(vhost_file: &Path) -> anyhow::Result<Vec<VirtualHost>> {
+    info!("get virtual hosts from file '{}'", vhost_file.display());
+
+    let directives = parse_config_file(vhost_file)?;
+
+    Ok(get_virtual_hosts_from_directives(&directives))
+}
+
+/// Discover virtual hosts loaded by nginx, starting from the root config file and
+/// following `include` directives.
+pub fn get_virtual_hosts_from_root_config(root_config: &Path) -> anyhow::Result<Vec<VirtualHost>> {
+    info!("get virtual hosts from root config '{}'", root_config.display());
+
+    let directives = load_config_with_includes(root_config)?;
+
+    Ok(get_virtual_hosts_from_directives(&directives))
+}
+
+pub fn get_stream_servers_from_file(config_file: &Path) -> anyhow::Result<Vec<StreamServer>> {
+    info!("get stream servers from file '{}'", config_file.display());
+
+    let directives = parse_config_file(config_file)?;
+
+    Ok(get_stream_servers(&directives))
+}
+
+/// Discover stream servers loaded by nginx, starting from the root config file and
+/// following `include` directives.
+pub fn get_stream_servers_from_root_config(root_config: &Path) -> anyhow::Result<Vec<StreamServer>> {
+    info!("get stream servers from root config '{}'", root_config.display());
+
+    let directives = load_config_with_includes(root_config)?;
+
+    Ok(get_stream_servers(&directives))
+}
+
+pub fn get_virtual_hosts_from_directives(directives: &[Directive]) -> Vec<VirtualHost> {
+    let upstreams = get_upstreams(directives);
+
+    get_server_blocks(directives).into_iter()
+        .flat_map(|server| get_virtual_hosts_from_server_block(server, &upstreams))
+        .collect()
+}
+
+/// Returns `server` blocks from top level (vhost files included into `http` context)
+/// and from `http` blocks. Stream servers are skipped.
+fn get_server_blocks(directives: &[Directive]) -> Vec<&Directive> {
+    let mut server_blocks: Vec<&Directive> = Vec::new();
+
+    for directive in directives {
+        if directive.name == SERVER_DIRECTIVE && directive.is_block() {
+            if is_stream_server(directive) {
+                debug!("server block at line {} is stream server, skip", directive.line);
+                continue;
+            }
+
+            server_blocks.push(directive);
+
+        } else if directive.name == HTTP_DIRECTIVE {
+            server_blocks.append(&mut get_server_blocks(directive.children()));
+        }
+    }
+
+    server_blocks
+}
+
+/// Returns vhost for every `listen` address and port of the server block.
+fn get_virtual_hosts_from_server_block(server: &Directive, upstreams: &Upstreams) -> Vec<VirtualHost> {
+    trace!("server block at line {}", server.line);
+
+    let annotations = get_annotations(server.children().iter()
+        .filter(|directive| directive.name == ANNOTATION_DIRECTIVE)
+        .map(|directive| directive.args.as_slice()));
+
+    if annotations.ignore {
+        debug!("server block at line {} is ignored by annotation", server.line);
+        return Vec::new();
+    }
+
+    let ssl_enabled = is_legacy_ssl_enabled(server);
+
+    let listen_directives = server.children().iter()
+        .filter(|directive| directive.name == LISTEN_DIRECTIVE)
+        .filter_map(get_listen);
+
+    let mut listens: Vec<Listen> = Vec::new();
+
+    for listen in listen_directives {
+        let known_listen = listens.iter_mut().find(
+            |known_listen| known_listen.address == listen.address && known_listen.port == listen.port);
+
+        match known_listen {
+            Some(known_listen) => {
+                known_listen.ssl |= listen.ssl;
+                known_listen.default_server |= listen.default_server;
+            }
+            None => listens.push(listen)
+        }
+    }
+
+    let server_name = server.children().iter()
+        .find(|directive| directive.name == SERVER_NAME_DIRECTIVE);
+
+    let mut server_names = server_name.map(get_server_names).unwrap_or_default();
+
+    if listens.is_empty() {
+        debug!("server block at line {} doesn't have port, skip", server.line);
+        return Vec::new();
+    }
+
+    let default_server = server_name.is_none_or(is_catch_all_server_name);
+
+    if !default_server && server_names.is_empty() {
+        debug!("server block at line {} doesn't have supported names, skip", server.line);
+        return Vec::new();
+    }
+
+    if default_server {
+        debug!("server block at line {} is catch-all default server (default_server: {})",
+               server.line, listens.iter().any(|listen| listen.default_server));
+    }
+
+    let domain = if default_server { String::new() } else { server_names.remove(0) };
+
+    debug!("domain found {domain}, listen {:?}, aliases {:?}", listens, server_names);
+
+    let backends = get_backends(server, upstreams);
+    let certificate = get_certificate(server);
+    let paths = get_paths(server, &annotations);
+
+    if !paths.is_empty() {
+        debug!("monitoring paths found {:?}", paths);
+    }
+
+    if !backends.is_empty() {
+        debug!("backends found {:?}", backends);
+    }
+
+    listens.into_iter()
+        .map(|listen| {
+            let mut vhost = VirtualHost {
+                domain: domain.to_string(), port: listen.port, tls: listen.ssl || ssl_enabled,
+                address: listen.address, aliases: server_names.clone(), redirect: None,
+                default_server, backends: backends.clone(), certificate: certificate.clone(),
+                paths: paths.clone(), display_name: annotations.name.clone(),
+                expected_status: annotations.expected_status, tags: annotations.tags.clone(),
+                alias_responses: HashMap::new()
+            };
+
+            vhost.alias_responses = server_names.iter()
+                .filter(|alias| is_valid_domain_name(alias))
+                .map(|alias| {
+                    let alias_vhost = VirtualHost { domain: alias.to_string(), ..vhost.clone() };
+                    (alias.to_string(), get_name_response(server, &alias_vhost))
+                })
+                .collect();
+
+            let response = get_name_response(server, &vhost);
+            vhost.redirect = response.redirect;
+            vhost.expected_status = response.expected_status;
+
+            if let Some(redirect) = &vhost.redirect {
+                debug!("redirect {} to '{}' was detected for server block at line {}",
+                       redirect.status, redirect.target, server.line);
+            }
+
+            vhost
+        })
+        .collect()
+}
+
+/// Redirect for the vhost name, expected status is inferred if the name isn't redirected
+/// and annotation doesn't set it.
+fn get_name_response(server: &Directive, vhost: &VirtualHost) -> NameResponse {
+    let redirect = get_redirect(server, vhost);
+
+    let expected_status = match vhost.expected_status {
+        None if redirect.is_none() => get_expected_status(server, vhost),
+        expected_status => expected_status
+    };
+
+    NameResponse { redirect, expected_status }
+}
+
+/// Paths from `# vhdt: path=...` annotations of the server block. Without annotations
+/// exact match locations of health check endpoints are used: `location = /healthz`.
+fn get_paths(server: &Directive, annotations: &Annotations) -> Vec<String> {
+    if !annotations.paths.is_empty() {
+        return annotations.paths.clone();
+    }
+
+    let mut paths: Vec<String> = Vec::new();
+
+    for path in get_health_check_locations(server) {
+        push_unique(&mut paths, &path);
+    }
+
+    paths
+}
+
+fn get_health_check_locations(block: &Directive) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+
+    for location in block.children().iter().filter(|directive| directive.name == LOCATION_DIRECTIVE) {
+        match location.args.as_slice() {
+            [modifier, path] if modifier == "=" && is_health_check_path(path) => paths.push(path.to_string()),
+            _ => paths.append(&mut get_health_check_locations(location))
+        }
+    }
+
+    paths
+}
+
+/// `/healthz`, `/api/status`, `/ping`, etc.
+fn is_health_check_path(path: &str) -> bool {
+    let last_segment = path.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+
+    path.starts_with('/') && HEALTH_CHECK_PATH_SEGMENTS.contains(&last_segment)
+}
+
+/// First `ssl_certificate` of the server block, paths with variables aren't supported
+fn get_certificate(server: &Directive) -> Option<Certificate> {
+    let path = server.children().iter()
+        .find(|directive| directive.name == SSL_CERTIFICATE_DIRECTIVE)
+        .and_then(|directive| directive.args.first())?;
+
+    if path.contains('$') || path.starts_with("data:") {
+        debug!("certificate '{path}' isn't a file, skip");
+        return None;
+    }
+
+    Some(Certificate { path: path.to_string(), ..Default::default() })
+}
+
+/// Legacy `ssl on;` enables TLS for all `listen` directives of the server block.
+fn is_legacy_ssl_enabled(server: &Directive) -> bool {
+    server.children().iter()
+        .any(|directive| directive.name == SSL_DIRECTIVE && directive.args == ["on"])
+}
+
+#[derive(Debug)]
+struct Listen {
+    address: Option<String>,
+    port: i32,
+    ssl: bool,
+
+    /// `default_server` parameter (`default` in old versions)
+    default_server: bool,
+
+    /// Stream servers only
+    udp: bool
+}
+
+/// Supported formats: `listen 80;`, `listen 10.0.0.1:80;`, `listen [::]:443 ssl http2;`,
+/// `listen localhost;`, `listen 80 default_server;`. Port is 80 when omitted, unix sockets are skipped.
+fn get_listen(listen: &Directive) -> Option<Listen> {
+    let value = listen.args.first()?;
+
+    match parse_listen_address(value) {
+        Some(ListenAddress { address, port }) => {
+            let parameters = &listen.args[1..];
+
+            let ssl = parameters.iter().any(|arg| arg == SSL_LISTEN_PARAMETER);
+            let default_server = parameters.iter()
+                .any(|arg| DEFAULT_SERVER_LISTEN_PARAMETERS.contains(&arg.as_str()));
+            let udp = parameters.iter().any(|arg| arg == UDP_LISTEN_PARAMETER);
+
+            Some(Listen { address, port: port.unwrap_or(DEFAULT_LISTEN_PORT), ssl, default_server, udp })
+        }
+        None => {
+            debug!("unsupported listen value '{value}' at line {}", listen.line);
+            None
+        }
+    }
+}
+
+/// Valid names from `server_name` in lowercase Unicode form, unsupported names are skipped. Exact names go first,
+/// then wildcard (`*.example.com`, `www.example.*`) and regex (`~^(?<sub>.+)\.example\.com$`) names.
+///
+/// `.example.com` is a short form for `example.com` and `*.example.com`.
+fn get_server_names(server_name: &Directive) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut patterns: Vec<String> = Vec::new();
+
+    for value in &server_name.args {
+        if is_regex_name(value) {
+            push_unique(&mut patterns, value);
+            continue;
+        }
+
+        let name = match normalize_domain_name(value) {
+            Some(name) => name,
+            None => {
+                debug!("unsupported server name '{value}' at line {}", server_name.line);
+                continue;
+            }
+        };
+
+        if let Some(domain) = name.strip_prefix('.').filter(|domain| is_valid_domain_name(domain)) {
+            push_unique(&mut names, domain);
+            push_unique(&mut patterns, &format!("*.{domain}"));
+
+        } else if is_valid_domain_name(&name) {
+            push_unique(&mut names, &name);
+
+        } else if is_valid_wildcard_name(&name) {
+            push_unique(&mut patterns, &name);
+
+        } else {
+            debug!("unsupported server name '{value}' at line {}", server_name.line);
+        }
+    }
+
+    names.append(&mut patterns);
+
+    names
+}
+
+/// `server_name _;`, `server_name "";` or `server_name` without names
+fn is_catch_all_server_name(server_name: &Directive) -> bool {
+    server_name.args.iter().all(|name| name.is_empty() || name == CATCH_ALL_SERVER_NAME)
+}
+
+fn push_unique(names: &mut Vec<String>, name: &str) {
+    if !names.iter().any(|known_name| known_name == name) {
+        names.push(name.to_string());
+    }
+}
+
+/// Wildcard is supported at the start or at the end of the name only: `*.example.com`, `www.example.*`
+fn is_valid_wildcard_name(name: &str) -> bool {
+    name.strip_prefix("*.").or_else(|| name.strip_suffix(".*"))
+        .is_some_and(|domain| !domain.contains('*') && is_valid_domain_name(domain))
+}
+
+fn is_regex_name(name: &str) -> bool {
+    name.len() > 1 && name.starts_with('~')
+}
+
+#[cfg(test)]
+pub mod nginx_tests {
+    use std::path::Path;
+
+    use crate::domain::Redirect;
+    use crate::domain::StreamServer;
+    use crate::filter::{DefaultServerPolicy, expand_aliases, resolve_default_servers, resolve_wildcard_names,
+                        WildcardPolicy};
+    use crate::nginx::{get_stream_servers_from_file, get_stream_servers_from_root_config,
+                       get_virtual_hosts_from_file, get_virtual_hosts_from_root_config};
+    use crate::test_utils::assert_vhost_in_vec;
+    use crate::VirtualHost;
+
+    #[test]
+    fn support_ip_and_port() {
+        let vhost_file_path = Path::new("tests/nginx-vhosts/listen.conf");
+
+        match get_virtual_hosts_from_file(vhost_file_path) {
+            Ok(vhosts) => {
+                println!("{:?}", vhosts);
+                assert_eq!(vhosts.len(), 2);
+
+                let expected_domain1 = "qweqwe.ru";
+
+                let expected_vhost1 = VirtualHost {
+                    domain: expected_domain1.to_string(),
+                    port: 2345,
+                    ..Default::default()
+                };
+
+                assert_eq!(expected_vhost1.to_string(), vhosts.first().unwrap().to_string());
+
+                let expected_domain2 = "www.megatron2000.ru";
+
+                let expected_vhost2 = VirtualHost {
+                    domain: expected_domain2.to_string(),
+                    port: 443,
+                    ..Default::default()
+                };
+
+                assert_eq!(expected_vhost2.to_string(), vhosts.last().unwrap().to_string());
+            },
+            Err(_) => panic!("vhosts vec was expected")
+        }
+    }
+
+    #[test]
+    fn support_ssl_and_http2() {
+        let vhost_file_path = Path::new("tests/nginx-vhosts/ssl-and-http2.conf");
+
+        match get_virtual_hosts_from_file(vhost_file_path) {
+            Ok(vhosts) => {
+                println!("{:?}", vhosts);
+                assert_eq!(vhosts.len(), 4);
+
+                let expected_domain = "zabbix.com";
+
+                let expected_vhost1 = VirtualHost {
+                    domain: expected_domain.to_string(),
+                    port: 443,
+                    ..Default::default()
+                };
+
+                assert_eq!(expected_vhost1.to_string(), vhosts.first().unwrap().to_string());
+
+                let expected_vhost2 = VirtualHost {
+                    domain: expected_domain.to_string(),
+                    port: 10555,
+                    ..Default::default()
+                };
+
+                assert_eq!(expected_vhost2.to_string(), vhosts.get(1).unwrap().to_string());
+
+                let expected_vhost3 = VirtualHost {
+                    domain: expected_domain.to_string(),
+                    port: 2928,
+                    ..Default::default()
+                };
+
+                assert_eq!(expected_vhost3.to_string(), vhosts.get(2).unwrap().to_string());
+
+                let expected_vhost4 = VirtualHost {
+                    domain: expected_domain.to_string(),
+                    port: 32318,
+                    ..Default::default()
+                };
+
+                assert_eq!(expected_vhost4.to_string(), vhosts.get(3).unwrap().to_string());
+            },
+            Err(_) => panic!("vhosts vec was expected")
+        }
+    }
+
+    #[test]
+    fn vhosts_with_return_301_should_have_redirect() {
+        let vhost_file_path = Path::new("tests/nginx-vhosts/return-301.conf");
+
+        match get_virtual_hosts_from_file(vhost_file_path) {
+            Ok(vhosts) => {
+                println!("{:?}", vhosts);
+                assert_eq!(vhosts.len(), 2);
+
+                let redirect_vhost = vhosts.first().unwrap();
+                assert_eq!(redirect_vhost.domain, "whatever.ru");
+                assert_eq!(redirect_vhost.redirect, Some(Redirect {
+                    status: 301,
+                    target: "https://y345y345ywhatever.ru/".to_string(),
+                    keeps_host: false
+                }));
+
+                let expected_vhost = VirtualHost {
+                    domain: "dhl.de".to_string(),
+                    port: 80,
+                    ..Default::default()
+                };
+
+                assert_eq!(expected_vhost.to_string(), vhosts.get(1).unwrap().to_string());
+                assert!(vhosts.get(1).unwrap().redirect.is_none());
+            },
+            Err(_) => panic!("vhosts vec was expected")
+        }
+    }
+
+    #[test]
+    fn comments_should_be_respected() {
+        let vhost_file_path = Path::new("tests/nginx-vhosts/comments.conf");
+
+        match get_virtual_hosts_from_file(vhost_file_path) {
+            Ok(vhosts) => {
+                println!("{:?}", vhosts);
+                assert_eq!(vhosts.len(), 1);
+
+                let expected_vhost = VirtualHost {
+                    domain: "whatever.ru".to_string(),
+                    port: 80,
+                    ..Default::default()
+                };
+
+                assert_eq!(expected_vhost.to_string(), vhosts.first().unwrap().to_string());
+            },
+            Err(_) => panic!("vhosts vec was expected")
+        }
+    }
+
+    #[test]
+    fn server_without_server_name_property_should_be_default_server() {
+        let vhost_file_path = Path::new(
+            "tests/nginx-vhosts/without-server-name-property.conf"
+        );
+
+        match get_virtual_hosts_from_file(vhost_file_path) {
+            Ok(vhosts) => {
+                println!("{:?}", vhosts);
+                assert_eq!(vhosts.len(), 5);
+                assert!(vhosts.iter().all(|vhost| vhost.default_server && vhost.domain.is_empty()));
+            },
+            Err(_) => panic!("vhosts vec was expected")
+        }
+    }
+
+    #[test]
+    fn catch_all_servers_should_be_default_servers() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/default-server.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 4);
+
+        let default_ports: Vec<i32> = vhosts.iter()
+            .filter(|vhost| vhost.default_server)
+            .map(|vhost| vhost.port).collect();
+
+        assert_eq!(default_ports, vec![80, 80, 443]);
+
+        let redirect = vhosts[0].redirect.as_ref().unwrap();
+        assert_eq!(redirect.target, "https://$host/");
+        assert!(redirect.keeps_host);
+
+        let resolved_vhosts = resolve_default_servers(&vhosts[..1], DefaultServerPolicy::Ip);
+        assert_eq!(resolved_vhosts[0].redirect.as_ref().unwrap().target, "https://10.0.0.1/");
+
+        let named_vhost = vhosts.last().unwrap();
+        assert_eq!(named_vhost.domain, "example.com");
+        assert!(!named_vhost.default_server);
+    }
+
+    #[test]
+    fn servers_with_unsupported_names_should_not_be_default_servers() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/unsupported-names.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 1);
+        assert!(!vhosts[0].default_server);
+
+        let resolved_vhosts = resolve_wildcard_names(&vhosts, &WildcardPolicy::Skip);
+        assert!(resolve_default_servers(&resolved_vhosts, DefaultServerPolicy::Ip).is_empty());
+    }
+
+    #[test]
+    fn wildcard_and_regex_server_names_should_be_supported() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/wildcard-names.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 3);
+
+        assert_eq!(vhosts[0].domain, "example.com");
+        assert_eq!(vhosts[0].aliases, vec!["www.example.com", "mail.*", "*.example.com"]);
+
+        assert_eq!(vhosts[1].domain, "*.shop.example.com");
+        assert!(vhosts[1].aliases.is_empty());
+
+        assert_eq!(vhosts[2].domain, "~^(?<user>[a-z]+)\\.blog\\.example\\.com$");
+        assert!(!vhosts[2].default_server);
+    }
+
+    #[test]
+    fn international_and_uppercase_server_names_should_be_normalized() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/idn.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 2);
+
+        assert_eq!(vhosts[0].domain, "пример.рф");
+        assert_eq!(vhosts[0].aliases, vec!["www.пример.рф", "*.пример.рф"]);
+
+        assert_eq!(vhosts[1].domain, "example.com");
+        assert_eq!(vhosts[1].aliases, vec!["www.example.com"]);
+    }
+
+    #[test]
+    fn stream_servers_should_be_excluded_from_virtual_hosts() {
+        let config_file = Path::new("test-data/nginx-vhosts/stream.conf");
+
+        let vhosts = get_virtual_hosts_from_file(config_file).unwrap();
+        assert_eq!(vhosts.len(), 1);
+        assert_eq!(vhosts[0].domain, "example.com");
+
+        let stream_servers = get_stream_servers_from_file(config_file).unwrap();
+        assert_eq!(stream_servers, vec![StreamServer {
+            port: 6379, udp: false, address: None, upstream: Some("127.0.0.1:6380".to_string())
+        }]);
+    }
+
+    #[test]
+    fn stream_servers_should_be_collected_from_root_config() {
+        let root_config = Path::new("test-data/nginx-root/nginx.conf");
+
+        let stream_servers = get_stream_servers_from_root_config(root_config).unwrap();
+        println!("{:?}", stream_servers);
+
+        let ports: Vec<(Option<&str>, i32, bool)> = stream_servers.iter()
+            .map(|stream_server| (stream_server.address.as_deref(), stream_server.port, stream_server.udp))
+            .collect();
+
+        assert_eq!(ports, vec![(None, 5432, false), (Some("::"), 53, true)]);
+    }
+
+    #[test]
+    fn nested_blocks_and_multiline_directives_should_be_supported() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/nested-blocks.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 3);
+
+        assert_vhost_in_vec(&vhosts, "nested.ru", 443);
+        assert_vhost_in_vec(&vhosts, "multiline.ru", 8080);
+        assert_vhost_in_vec(&vhosts, "http-context.ru", 80);
+    }
+
+    #[test]
+    fn return_error_for_broken_config() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/broken.conf");
+        assert!(get_virtual_hosts_from_file(vhost_file_path).is_err());
+    }
+
+    #[test]
+    fn all_server_names_should_be_collected() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/multiple-server-names.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 2);
+
+        let first_vhost = vhosts.first().unwrap();
+        assert_eq!(first_vhost.domain, "example.com");
+        assert_eq!(first_vhost.aliases, vec!["api.example.com", "static.example.com"]);
+
+        let last_vhost = vhosts.last().unwrap();
+        assert_eq!(last_vhost.domain, "api.example.com");
+        assert!(last_vhost.aliases.is_empty());
+    }
+
+    #[test]
+    fn vhost_should_be_collected_for_every_listen_directive() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/multiple-listen.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 4);
+
+        assert_vhost_in_vec(&vhosts, "multi.ru", 80);
+        assert_vhost_in_vec(&vhosts, "multi.ru", 443);
+        assert_vhost_in_vec(&vhosts, "multi.ru", 8443);
+
+        assert!(vhosts.iter().any(
+            |vhost| vhost.port == 443 && vhost.address.as_deref() == Some("10.0.0.1")));
+    }
+
+    #[test]
+    fn tls_should_be_detected_by_listen_ssl_parameter() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/multiple-listen.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+
+        let tls_ports: Vec<i32> = vhosts.iter()
+            .filter(|vhost| vhost.tls).map(|vhost| vhost.port).collect();
+
+        assert_eq!(tls_ports, vec![443, 443, 8443]);
+    }
+
+    #[test]
+    fn tls_should_be_detected_by_legacy_ssl_directive() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/legacy-ssl.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 2);
+
+        let legacy_vhost = vhosts.first().unwrap();
+        assert_eq!(legacy_vhost.domain, "legacy.ru");
+        assert_eq!(legacy_vhost.port, 8443);
+        assert!(legacy_vhost.tls);
+        assert_eq!(legacy_vhost.certificate.as_ref().unwrap().path, "/etc/ssl/legacy.ru.pem");
+
+        let plain_vhost = vhosts.last().unwrap();
+        assert_eq!(plain_vhost.domain, "plain.ru");
+        assert!(!plain_vhost.tls);
+        assert!(plain_vhost.certificate.is_none());
+    }
+
+    #[test]
+    fn vhosts_should_be_collected_from_included_files_only() {
+        let root_config = Path::new("test-data/nginx-root/nginx.conf");
+
+        let vhosts = get_virtual_hosts_from_root_config(root_config).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 3);
+
+        assert_vhost_in_vec(&vhosts, "app.example.com", 443);
+        assert_vhost_in_vec(&vhosts, "blog.example.com", 80);
+        assert_vhost_in_vec(&vhosts, "admin.example.com", 8080);
+    }
+
+    #[test]
+    fn backends_should_be_resolved_with_upstreams_from_other_files() {
+        let root_config = Path::new("test-data/nginx-root/nginx.conf");
+
+        let vhosts = get_virtual_hosts_from_root_config(root_config).unwrap();
+        println!("{:?}", vhosts);
+
+        let blog_vhost = vhosts.iter().find(|vhost| vhost.domain == "blog.example.com").unwrap();
+
+        let backends: Vec<(&str, &str, Option<&str>)> = blog_vhost.backends.iter()
+            .map(|backend| (backend.proto.as_str(), backend.address.as_str(), backend.upstream.as_deref()))
+            .collect();
+
+        assert_eq!(backends, vec![
+            ("http", "10.0.0.10:8080", Some("blog_backend")),
+            ("http", "10.0.0.11:8080", Some("blog_backend")),
+        ]);
+
+        let admin_vhost = vhosts.iter().find(|vhost| vhost.domain == "admin.example.com").unwrap();
+        assert_eq!(admin_vhost.backends[0].address, "unix:/run/php/php-fpm.sock");
+
+        let app_vhost = vhosts.iter().find(|vhost| vhost.domain == "app.example.com").unwrap();
+        assert!(app_vhost.backends.is_empty());
+    }
+
+    #[test]
+    fn monitoring_paths_should_be_taken_from_annotations_or_health_check_locations() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/paths.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 2);
+
+        assert_eq!(vhosts[0].paths, vec!["/healthz", "/api/status"]);
+        assert_eq!(vhosts[1].paths, vec!["/ready", "/api/ping"]);
+    }
+
+    #[test]
+    fn annotations_should_be_applied_to_vhosts() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/annotations.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        assert_eq!(vhosts.len(), 2);
+
+        let billing_vhost = &vhosts[0];
+        assert_eq!(billing_vhost.domain, "billing.example.com");
+        assert_eq!(billing_vhost.display_name.as_deref(), Some("Billing API"));
+        assert_eq!(billing_vhost.expected_status, Some(401));
+        assert_eq!(billing_vhost.tags, vec!["team:payments", "env:prod"]);
+
+        let plain_vhost = &vhosts[1];
+        assert_eq!(plain_vhost.domain, "plain.example.com");
+        assert_eq!(plain_vhost.display_name, None);
+        assert!(plain_vhost.tags.is_empty());
+    }
+
+    #[test]
+    fn expected_status_should_be_inferred_from_server_block() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/expected-status.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        let statuses: Vec<(&str, Option<u16>)> = vhosts.iter()
+            .map(|vhost| (vhost.domain.as_str(), vhost.expected_status)).collect();
+
+        assert_eq!(statuses, vec![
+            ("example.com", Some(404)),
+            ("admin.example.com", Some(401)),
+            ("internal.example.com", Some(403)),
+            ("status.example.com", Some(200)),
+            ("www.example.com", None),
+        ]);
+    }
+
+    #[test]
+    fn redirect_and_expected_status_should_be_evaluated_for_every_alias() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/alias-redirect.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        let vhosts = expand_aliases(&vhosts, &WildcardPolicy::Skip);
+        println!("{:?}", vhosts);
+
+        let responses: Vec<(&str, Option<&str>, Option<u16>)> = vhosts.iter()
+            .map(|vhost| (
+                vhost.domain.as_str(),
+                vhost.redirect.as_ref().map(|redirect| redirect.target.as_str()),
+                vhost.expected_status
+            ))
+            .collect();
+
+        assert_eq!(responses, vec![
+            ("www.example.com", Some("https://example.com/"), None),
+            ("example.com", None, Some(401)),
+        ]);
+    }
+
+    #[test]
+    fn ipv6_and_hostname_listen_addresses_should_be_supported() {
+        let vhost_file_path = Path::new("test-data/nginx-vhosts/ipv6.conf");
+
+        let vhosts = get_virtual_hosts_from_file(vhost_file_path).unwrap();
+        println!("{:?}", vhosts);
+
+        let listens: Vec<(Option<&str>, i32, bool)> = vhosts.iter()
+            .map(|vhost| (vhost.address.as_deref(), vhost.port, vhost.tls)).collect();
+
+        assert_eq!(listens, vec![
+            (None, 443, true),
+            (Some("::"), 443, true),
+            (Some("2001:db8::1"), 8080, false),
+            (None, 8081, false),
+            (Some("localhost"), 8082, false),
+        ]);
+    }
+}
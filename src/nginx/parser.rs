@@ -0,0 +1,429 @@
+use std::fs;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+
+use anyhow::{anyhow, Context};
+
+use crate::annotation::{ANNOTATION_DIRECTIVE, parse_annotation};
+
+/// nginx config directive.
+///
+/// Simple directive:
+///
+/// ```nginx
+/// listen 443 ssl;
+/// ```
+///
+/// Block directive (`http`, `server`, `location`, `if`, etc.):
+///
+/// ```nginx
+/// location / {
+///     return 404;
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Directive {
+    pub name: String,
+
+    pub args: Vec<String>,
+
+    /// Line number where directive starts (starts with 1)
+    pub line: usize,
+
+    /// Nested directives, `None` for simple directives
+    pub block: Option<Vec<Directive>>
+}
+
+impl Directive {
+    pub fn is_block(&self) -> bool {
+        self.block.is_some()
+    }
+
+    pub fn children(&self) -> &[Directive] {
+        match &self.block {
+            Some(children) => children,
+            None => &[]
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+
+    /// `# vhdt: ...` comment
+    Annotation(Vec<String>),
+
+    Semicolon,
+    BlockStart,
+    BlockEnd
+}
+
+#[derive(Debug)]
+struct LineToken {
+    token: Token,
+    line: usize
+}
+
+pub fn parse_config_file(config_file: &Path) -> anyhow::Result<Vec<Directive>> {
+    let input = fs::read_to_string(config_file)
+        .with_context(|| format!("couldn't read config file '{}'", config_file.display()))?;
+
+    parse_config(&input)
+        .with_context(|| format!("couldn't parse config file '{}'", config_file.display()))
+}
+
+pub fn parse_config(input: &str) -> anyhow::Result<Vec<Directive>> {
+    let tokens = tokenize(input)?;
+    let mut position: usize = 0;
+    parse_block(&tokens, &mut position, None)
+}
+
+fn parse_block(tokens: &[LineToken], position: &mut usize,
+               block_start_line: Option<usize>) -> anyhow::Result<Vec<Directive>> {
+    let mut directives: Vec<Directive> = Vec::new();
+
+    let mut name: Option<(String, usize)> = None;
+    let mut args: Vec<String> = Vec::new();
+
+    while let Some(line_token) = tokens.get(*position) {
+        *position += 1;
+
+        match &line_token.token {
+            Token::Word(value) => {
+                if name.is_none() {
+                    name = Some((value.to_string(), line_token.line));
+                } else {
+                    args.push(value.to_string());
+                }
+            }
+            Token::Annotation(annotation_args) => {
+                match name {
+                    Some(_) => debug!("annotation inside directive at line {} is ignored", line_token.line),
+                    None => directives.push(Directive {
+                        name: ANNOTATION_DIRECTIVE.to_string(), args: annotation_args.clone(),
+                        line: line_token.line, block: None
+                    })
+                }
+            }
+            Token::Semicolon => {
+                let (directive_name, line) = name.take().ok_or_else(
+                    || anyhow!("unexpected ';' at line {}", line_token.line))?;
+
+                directives.push(Directive {
+                    name: directive_name, args: std::mem::take(&mut args), line, block: None
+                });
+            }
+            Token::BlockStart => {
+                let (directive_name, line) = name.take().ok_or_else(
+                    || anyhow!("unexpected '{{' at line {}", line_token.line))?;
+
+                let children = parse_block(tokens, position, Some(line_token.line))?;
+
+                directives.push(Directive {
+                    name: directive_name, args: std::mem::take(&mut args), line,
+                    block: Some(children)
+                });
+            }
+            Token::BlockEnd => {
+                if let Some((directive_name, line)) = name {
+                    return Err(anyhow!("unexpected '}}' at line {}, directive '{}' at line {} \
+                                        isn't terminated by ';'",
+                                       line_token.line, directive_name, line));
+                }
+
+                return match block_start_line {
+                    Some(_) => Ok(directives),
+                    None => Err(anyhow!("unexpected '}}' at line {}", line_token.line))
+                };
+            }
+        }
+    }
+
+    if let Some((directive_name, line)) = name {
+        return Err(anyhow!("unexpected end of file, directive '{directive_name}' \
+                            at line {line} isn't terminated by ';'"));
+    }
+
+    match block_start_line {
+        Some(line) => Err(anyhow!("unexpected end of file, block opened at line {line} \
+                                   isn't closed")),
+        None => Ok(directives)
+    }
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<LineToken>> {
+    let mut tokens: Vec<LineToken> = Vec::new();
+
+    let mut chars = input.chars().peekable();
+    let mut line: usize = 1;
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\n' => {
+                line += 1;
+                chars.next();
+            }
+            '#' => {
+                chars.next();
+
+                let mut comment = String::new();
+
+                while let Some(&comment_char) = chars.peek() {
+                    if comment_char == '\n' {
+                        break;
+                    }
+                    comment.push(comment_char);
+                    chars.next();
+                }
+
+                if let Some(args) = parse_annotation(&comment) {
+                    tokens.push(LineToken { token: Token::Annotation(args), line });
+                }
+            }
+            ';' | '{' | '}' => {
+                let token = match c {
+                    ';' => Token::Semicolon,
+                    '{' => Token::BlockStart,
+                    _ => Token::BlockEnd
+                };
+                tokens.push(LineToken { token, line });
+                chars.next();
+            }
+            '"' | '\'' => {
+                let start_line = line;
+                chars.next();
+                let value = read_quoted_word(&mut chars, c, &mut line).ok_or_else(
+                    || anyhow!("unterminated quoted string at line {start_line}"))?;
+                tokens.push(LineToken { token: Token::Word(value), line: start_line });
+            }
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let start_line = line;
+                let value = read_word(&mut chars, &mut line)?;
+                tokens.push(LineToken { token: Token::Word(value), line: start_line });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads value till closing quote. Returns `None` for unterminated string.
+fn read_quoted_word(chars: &mut Peekable<Chars>, quote: char, line: &mut usize) -> Option<String> {
+    let mut value = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let escaped_char = chars.next()?;
+                push_escaped_char(&mut value, escaped_char, line);
+            }
+            _ if c == quote => return Some(value),
+            _ => {
+                if c == '\n' {
+                    *line += 1;
+                }
+                value.push(c)
+            }
+        }
+    }
+
+    None
+}
+
+/// Reads unquoted value. Variables with braces (`${host}`) are part of the value.
+fn read_word(chars: &mut Peekable<Chars>, line: &mut usize) -> anyhow::Result<String> {
+    let mut value = String::new();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == ';' || c == '}' || (c == '{' && !value.ends_with('$')) {
+            break;
+        }
+
+        chars.next();
+
+        match c {
+            '\\' => {
+                if let Some(escaped_char) = chars.next() {
+                    push_escaped_char(&mut value, escaped_char, line);
+                }
+            }
+            '{' => {
+                value.push(c);
+
+                loop {
+                    match chars.next() {
+                        Some('}') => {
+                            value.push('}');
+                            break;
+                        }
+                        Some(variable_char) if !variable_char.is_whitespace() =>
+                            value.push(variable_char),
+                        _ => return Err(anyhow!("invalid variable name '{value}' at line {line}"))
+                    }
+                }
+            }
+            _ => value.push(c)
+        }
+    }
+
+    Ok(value)
+}
+
+/// nginx unescapes only quotes and backslash, other sequences are kept as is
+/// (regular expressions rely on it).
+fn push_escaped_char(value: &mut String, escaped_char: char, line: &mut usize) {
+    match escaped_char {
+        '"' | '\'' | '\\' => value.push(escaped_char),
+        _ => {
+            if escaped_char == '\n' {
+                *line += 1;
+            }
+            value.push('\\');
+            value.push(escaped_char);
+        }
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use std::path::Path;
+
+    use crate::nginx::parser::{parse_config, parse_config_file};
+
+    #[test]
+    fn nested_blocks_should_be_parsed_with_line_numbers() {
+        let input = "http {\n    server {\n        listen 80;\n\n        location / {\n            \
+                     return 404;\n        }\n    }\n}\n";
+
+        let directives = parse_config(input).unwrap();
+
+        assert_eq!(directives.len(), 1);
+
+        let http = directives.first().unwrap();
+        assert_eq!(http.name, "http");
+        assert_eq!(http.line, 1);
+
+        let server = http.children().first().unwrap();
+        assert_eq!(server.name, "server");
+        assert_eq!(server.line, 2);
+        assert_eq!(server.children().len(), 2);
+
+        let listen = server.children().first().unwrap();
+        assert_eq!(listen.name, "listen");
+        assert_eq!(listen.args, vec!["80"]);
+        assert_eq!(listen.line, 3);
+        assert!(!listen.is_block());
+
+        let location = server.children().last().unwrap();
+        assert_eq!(location.name, "location");
+        assert_eq!(location.args, vec!["/"]);
+        assert_eq!(location.line, 5);
+
+        let return_directive = location.children().first().unwrap();
+        assert_eq!(return_directive.args, vec!["404"]);
+        assert_eq!(return_directive.line, 6);
+    }
+
+    #[test]
+    fn block_start_on_next_line_should_be_supported() {
+        let directives = parse_config("server\n{\n    listen 80;\n}").unwrap();
+
+        let server = directives.first().unwrap();
+        assert_eq!(server.name, "server");
+        assert!(server.args.is_empty());
+        assert_eq!(server.children().len(), 1);
+    }
+
+    #[test]
+    fn multiline_directives_should_be_supported() {
+        let input = "server_name example.com\n            www.example.com\n            api.example.com;";
+        let directives = parse_config(input).unwrap();
+
+        let server_name = directives.first().unwrap();
+        assert_eq!(server_name.args, vec!["example.com", "www.example.com", "api.example.com"]);
+        assert_eq!(server_name.line, 1);
+    }
+
+    #[test]
+    fn quoted_values_should_be_single_args() {
+        let input = r#"add_header X-Test "a ; b { c }"; log_format main 'it\'s';"#;
+        let directives = parse_config(input).unwrap();
+
+        assert_eq!(directives.len(), 2);
+        assert_eq!(directives.first().unwrap().args, vec!["X-Test", "a ; b { c }"]);
+        assert_eq!(directives.last().unwrap().args, vec!["main", "it's"]);
+    }
+
+    #[test]
+    fn comments_should_be_ignored() {
+        let input = "server { # server { \n    listen 80; # listen 81;\n    #}\n}";
+        let directives = parse_config(input).unwrap();
+
+        assert_eq!(directives.len(), 1);
+
+        let server = directives.first().unwrap();
+        assert_eq!(server.children().len(), 1);
+        assert_eq!(server.children().first().unwrap().args, vec!["80"]);
+    }
+
+    #[test]
+    fn annotation_comments_should_be_kept_as_directives() {
+        let input = "server {\n    # vhdt: path=/healthz\n    listen 80; # vhdt: path=/ping\n    #vhdt:\n}";
+        let directives = parse_config(input).unwrap();
+
+        let server = directives.first().unwrap();
+
+        let annotations: Vec<(usize, &[String])> = server.children().iter()
+            .filter(|directive| directive.name == "#vhdt")
+            .map(|directive| (directive.line, directive.args.as_slice()))
+            .collect();
+
+        assert_eq!(annotations, vec![
+            (2, &["path=/healthz".to_string()][..]), (3, &["path=/ping".to_string()][..]), (4, &[][..])
+        ]);
+    }
+
+    #[test]
+    fn variables_with_braces_should_be_part_of_value() {
+        let directives = parse_config("return 301 https://${host}$request_uri;").unwrap();
+
+        assert_eq!(directives.first().unwrap().args, vec!["301", "https://${host}$request_uri"]);
+    }
+
+    #[test]
+    fn regex_escape_sequences_should_be_kept() {
+        let directives = parse_config(r"server_name ~^www\.example\.com$;").unwrap();
+
+        assert_eq!(directives.first().unwrap().args, vec![r"~^www\.example\.com$"]);
+    }
+
+    #[test]
+    fn return_error_for_unclosed_block() {
+        assert!(parse_config("server {\n    listen 80;\n").is_err());
+    }
+
+    #[test]
+    fn return_error_for_unexpected_block_end() {
+        assert!(parse_config("listen 80;\n}").is_err());
+    }
+
+    #[test]
+    fn return_error_for_directive_without_semicolon() {
+        assert!(parse_config("server {\n    listen 80\n}").is_err());
+    }
+
+    #[test]
+    fn return_error_for_unterminated_quoted_string() {
+        assert!(parse_config("add_header X-Test \"abc;").is_err());
+    }
+
+    #[test]
+    fn return_error_for_unknown_file() {
+        assert!(parse_config_file(Path::new("does-not-exist.conf")).is_err());
+    }
+}
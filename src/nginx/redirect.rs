@@ -0,0 +1,451 @@
+use regex::{Captures, Regex, RegexBuilder};
+
+use crate::domain::{Redirect, UNRESOLVED_HOST, VirtualHost};
+use crate::nginx::parser::Directive;
+use crate::site::get_url;
+
+const RETURN_DIRECTIVE: &str = "return";
+const REWRITE_DIRECTIVE: &str = "rewrite";
+const IF_DIRECTIVE: &str = "if";
+
+const REDIRECT_STATUSES: [u16; 5] = [301, 302, 303, 307, 308];
+
+/// nginx closes connection without response
+const CLOSE_CONNECTION_STATUS: u16 = 444;
+
+/// Request uri of checked request
+const ROOT_URI: &str = "/";
+
+const PERMANENT_FLAG: &str = "permanent";
+const REDIRECT_FLAG: &str = "redirect";
+
+/// Variables with request host
+const HOST_VARIABLES: [&str; 2] = ["host", "http_host"];
+
+/// Result of rewrite directives execution
+enum Action {
+    /// Request processing goes on with the next directive
+    Continue,
+
+    /// Response is known: redirect, another status (`return 404`) or internal rewrite
+    Stop(Response)
+}
+
+enum Response {
+    Redirect(Redirect),
+
+    /// `return 404;`, `return 200 "ok";`
+    Status(u16),
+
+    /// Internal rewrite or connection close (`return 444`)
+    Unknown
+}
+
+/// Executes `return`, `rewrite` and `if` directives of server block in order for request to `/`,
+/// like nginx does before location lookup. Directives inside `location` are ignored.
+///
+/// `if` conditions on host, scheme, port and request method are evaluated for the vhost,
+/// blocks with other conditions are skipped.
+pub fn get_redirect(server: &Directive, vhost: &VirtualHost) -> Option<Redirect> {
+    match execute(server.children(), vhost, &[], &mut ROOT_URI.to_string()) {
+        Action::Stop(Response::Redirect(redirect)) => Some(redirect),
+        _ => None
+    }
+}
+
+/// Status of `return` executed for request to `/` by directives of server or location block,
+/// see [`get_redirect`]
+pub fn get_return_status(directives: &[Directive], vhost: &VirtualHost) -> Option<u16> {
+    match execute(directives, vhost, &[], &mut ROOT_URI.to_string()) {
+        Action::Stop(Response::Redirect(redirect)) => Some(redirect.status),
+        Action::Stop(Response::Status(status)) => Some(status),
+        _ => None
+    }
+}
+
+/// `uri` is changed by internal rewrites without flag, the next rewrites are matched with it
+fn execute(directives: &[Directive], vhost: &VirtualHost, captures: &[String], uri: &mut String) -> Action {
+    for directive in directives {
+        let action = match directive.name.as_str() {
+            RETURN_DIRECTIVE => Action::Stop(get_return_response(directive, vhost, captures)),
+            REWRITE_DIRECTIVE => execute_rewrite(directive, vhost, uri),
+            IF_DIRECTIVE if directive.is_block() => {
+                match evaluate_condition(&directive.args, vhost) {
+                    Some(if_captures) => execute(directive.children(), vhost, &if_captures, uri),
+                    None => Action::Continue
+                }
+            }
+            _ => Action::Continue
+        };
+
+        if let Action::Stop(_) = action {
+            return action;
+        }
+    }
+
+    Action::Continue
+}
+
+/// `return 301 https://...`, `return 302 /path`, `return https://...` (302), `return 404`
+fn get_return_response(directive: &Directive, vhost: &VirtualHost, captures: &[String]) -> Response {
+    let (status, target) = match directive.args.as_slice() {
+        [target] if is_absolute_url(target) => (302, Some(target)),
+        [status] => (status.parse::<u16>().unwrap_or_default(), None),
+        [status, target] => (status.parse::<u16>().unwrap_or_default(), Some(target)),
+        _ => return Response::Unknown
+    };
+
+    match target {
+        Some(target) if REDIRECT_STATUSES.contains(&status) =>
+            Response::Redirect(create_redirect(status, target, vhost, captures)),
+        _ if (100..=599).contains(&status) && status != CLOSE_CONNECTION_STATUS => Response::Status(status),
+        _ => Response::Unknown
+    }
+}
+
+/// `rewrite ^ https://example.com$request_uri permanent;`. Internal rewrite without flag changes
+/// `uri` and processing goes on, `last` and `break` stop it.
+fn execute_rewrite(directive: &Directive, vhost: &VirtualHost, uri: &mut String) -> Action {
+    let (pattern, replacement) = match directive.args.as_slice() {
+        [pattern, replacement, ..] => (pattern, replacement),
+        _ => return Action::Continue
+    };
+
+    let regex = match Regex::new(pattern) {
+        Ok(regex) => regex,
+        Err(e) => {
+            debug!("unsupported rewrite regex '{pattern}' at line {}: {}", directive.line, e);
+            return Action::Continue;
+        }
+    };
+
+    let captures = match regex.captures(uri) {
+        Some(captures) => get_captures(&captures),
+        None => return Action::Continue
+    };
+
+    let status = match directive.args.get(2).map(|flag| flag.as_str()) {
+        Some(PERMANENT_FLAG) => 301,
+        Some(REDIRECT_FLAG) => 302,
+        _ if is_absolute_url(replacement) => 302,
+        None => {
+            let target = resolve_target(replacement, vhost, &captures);
+            *uri = target.split('?').next().unwrap_or_default().to_string();
+
+            debug!("internal rewrite at line {} to '{uri}', processing goes on", directive.line);
+            return Action::Continue;
+        }
+        Some(_) => {
+            debug!("internal rewrite at line {}, request to '/' isn't a redirect", directive.line);
+            return Action::Stop(Response::Unknown);
+        }
+    };
+
+    // trailing '?' disables request arguments
+    let target = replacement.strip_suffix('?').unwrap_or(replacement);
+
+    Action::Stop(Response::Redirect(create_redirect(status, target, vhost, &captures)))
+}
+
+fn create_redirect(status: u16, target: &str, vhost: &VirtualHost, captures: &[String]) -> Redirect {
+    let target_url = resolve_target(target, vhost, captures);
+
+    if is_absolute_url(target) {
+        Redirect {
+            status,
+            target: target_url,
+            keeps_host: HOST_VARIABLES.iter().any(|variable| has_variable(target, variable))
+        }
+
+    } else {
+        // relative redirect is sent to the same host
+        let host = if vhost.domain.is_empty() { UNRESOLVED_HOST } else { &vhost.domain };
+
+        Redirect {
+            status,
+            target: format!("{}{}", get_url(host, vhost.port, vhost.tls), target_url),
+            keeps_host: true
+        }
+    }
+}
+
+/// `if ($host = example.com)`, `if ($scheme != "https")`, `if ($host ~* ^www\.(.+)$)`.
+///
+/// Returns regex captures for true condition, `None` for false or unsupported condition.
+fn evaluate_condition(args: &[String], vhost: &VirtualHost) -> Option<Vec<String>> {
+    let joined = args.join(" ");
+
+    let condition = joined.trim().strip_prefix('(')
+                                 .and_then(|condition| condition.strip_suffix(')'))?.trim();
+
+    let (variable, rest) = match condition.find(char::is_whitespace) {
+        Some(end) => (&condition[..end], condition[end..].trim_start()),
+        None => (condition, "")
+    };
+
+    let value = match variable.strip_prefix('$').and_then(|name| get_variable(name, vhost)) {
+        Some(value) => value,
+        None => {
+            debug!("unsupported condition '{condition}', skip 'if' block");
+            return None;
+        }
+    };
+
+    if rest.is_empty() {
+        return if value.is_empty() || value == "0" { None } else { Some(Vec::new()) };
+    }
+
+    let (operator, expected) = match rest.find(char::is_whitespace) {
+        Some(end) => (&rest[..end], rest[end..].trim_start()),
+        None => (rest, "")
+    };
+
+    let expected = expected.trim_matches(|c| c == '"' || c == '\'');
+
+    match operator {
+        "=" => (value == expected).then(Vec::new),
+        "!=" => (value != expected).then(Vec::new),
+        "~" | "~*" | "!~" | "!~*" => {
+            let regex = RegexBuilder::new(expected)
+                .case_insensitive(operator.ends_with('*'))
+                .build().ok()?;
+
+            let captures = regex.captures(&value);
+
+            if operator.starts_with('!') {
+                captures.is_none().then(Vec::new)
+
+            } else {
+                captures.map(|captures| get_captures(&captures))
+            }
+        }
+        _ => {
+            debug!("unsupported condition '{condition}', skip 'if' block");
+            None
+        }
+    }
+}
+
+/// Values for request to `/` with vhost domain in `Host` header
+fn get_variable(name: &str, vhost: &VirtualHost) -> Option<String> {
+    let value = match name {
+        "host" | "http_host" | "server_name" => vhost.domain.to_string(),
+        "scheme" => get_scheme(vhost).to_string(),
+        "https" => if vhost.tls { "on".to_string() } else { String::new() },
+        "server_port" => vhost.port.to_string(),
+        "request_uri" | "uri" | "document_uri" => "/".to_string(),
+        "is_args" | "args" | "query_string" => String::new(),
+        "request_method" => "GET".to_string(),
+        _ => return None
+    };
+
+    Some(value)
+}
+
+/// Default server domain is unknown until it's resolved by policy
+fn is_host_variable(name: &str) -> bool {
+    HOST_VARIABLES.contains(&name) || name == "server_name"
+}
+
+fn get_scheme(vhost: &VirtualHost) -> &str {
+    if vhost.tls { "https" } else { "http" }
+}
+
+/// `$0` - whole match, `$1`..`$9` - groups
+fn get_captures(captures: &Captures) -> Vec<String> {
+    captures.iter()
+        .map(|group| group.map(|group| group.as_str().to_string()).unwrap_or_default())
+        .collect()
+}
+
+/// Target for request to `/`: `https://$host$request_uri` -> `https://example.com/`
+fn resolve_target(target: &str, vhost: &VirtualHost, captures: &[String]) -> String {
+    let mut result = String::new();
+    let mut rest = target;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+
+        let reference = &rest[start + 1..];
+
+        let (name, length) = match reference.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0)
+            },
+            None if reference.starts_with(|c: char| c.is_ascii_digit()) => (&reference[..1], 1),
+            None => {
+                let end = reference.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                                   .unwrap_or(reference.len());
+                (&reference[..end], end)
+            }
+        };
+
+        let value = match name.parse::<usize>() {
+            Ok(index) => captures.get(index).cloned(),
+            Err(_) if vhost.domain.is_empty() && is_host_variable(name) => Some(UNRESOLVED_HOST.to_string()),
+            Err(_) => get_variable(name, vhost)
+        };
+
+        match value {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(&rest[start..start + 1 + length])
+        }
+
+        rest = &reference[length..];
+    }
+
+    result.push_str(rest);
+
+    result
+}
+
+fn is_absolute_url(target: &str) -> bool {
+    target.starts_with("http://") || target.starts_with("https://") ||
+        target.starts_with("$scheme://") || target.starts_with("${scheme}://")
+}
+
+fn has_variable(value: &str, name: &str) -> bool {
+    value.contains(&format!("${{{name}}}")) ||
+        value.match_indices(&format!("${name}")).any(|(position, matched)| {
+            !value[position + matched.len()..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+#[cfg(test)]
+mod redirect_tests {
+    use crate::domain::VirtualHost;
+    use crate::nginx::parser::parse_config;
+    use crate::nginx::redirect::get_redirect;
+
+    fn get_vhost(tls: bool) -> VirtualHost {
+        VirtualHost {
+            domain: "example.com".to_string(), port: if tls { 443 } else { 80 }, tls,
+            ..Default::default()
+        }
+    }
+
+    fn get_server_redirect(server_body: &str, tls: bool) -> Option<(u16, String)> {
+        let server = &parse_config(&format!("server {{\n{server_body}\n}}")).unwrap()[0];
+
+        get_redirect(server, &get_vhost(tls)).map(|redirect| (redirect.status, redirect.target))
+    }
+
+    #[test]
+    fn redirect_target_should_be_resolved_for_root_request() {
+        let cases = [
+            ("return 301 https://$host$request_uri;", "https://example.com/", true),
+            ("return 301 https://${server_name}${request_uri};", "https://example.com/", false),
+            ("return 301 $scheme://www.example.com$uri$is_args$args;", "http://www.example.com/", false),
+            ("return 301 https://example.org$custom;", "https://example.org$custom", false),
+            ("return 301 https://$http_host$request_uri;", "https://example.com/", true),
+            ("return 301 https://$hostname/;", "https://$hostname/", false),
+        ];
+
+        for (row, target, keeps_host) in cases {
+            let server = &parse_config(&format!("server {{ {row} }}")).unwrap()[0];
+
+            let redirect = get_redirect(server, &get_vhost(false)).unwrap();
+
+            assert_eq!(redirect.status, 301);
+            assert_eq!(redirect.target, target, "row '{}'", row);
+            assert_eq!(redirect.keeps_host, keeps_host, "row '{}'", row);
+        }
+    }
+
+    #[test]
+    fn redirect_inside_location_should_be_ignored() {
+        let server = &parse_config("server { location / { return 301 https://$host; } }").unwrap()[0];
+        assert!(get_redirect(server, &get_vhost(false)).is_none());
+
+        let body = "location /foo {\n\n    return 301 https://example.org;\n}\n\nlisten 80;";
+        assert_eq!(get_server_redirect(body, false), None);
+    }
+
+    #[test]
+    fn all_redirect_statuses_should_be_supported() {
+        let cases = [
+            ("return 302 https://example.org;", 302, "https://example.org"),
+            ("return 307 https://example.org/;", 307, "https://example.org/"),
+            ("return 308 https://example.org/;", 308, "https://example.org/"),
+            ("return https://example.org/;", 302, "https://example.org/"),
+            ("return 301 /maintenance.html;", 301, "http://example.com/maintenance.html"),
+        ];
+
+        for (body, status, target) in cases {
+            assert_eq!(get_server_redirect(body, false), Some((status, target.to_string())),
+                       "body '{}'", body);
+        }
+
+        for body in ["return 404;", "return 200 'ok';", "return 444;"] {
+            assert_eq!(get_server_redirect(body, false), None, "body '{}'", body);
+        }
+    }
+
+    #[test]
+    fn rewrite_redirects_should_be_supported() {
+        let cases = [
+            ("rewrite ^ https://$host$request_uri? permanent;", 301, "https://example.com/"),
+            ("rewrite ^/(.*)$ https://example.org/$1 redirect;", 302, "https://example.org/"),
+            ("rewrite ^(.*)$ https://example.org$1;", 302, "https://example.org/"),
+            ("rewrite ^/$ /new/ permanent;", 301, "http://example.com/new/"),
+        ];
+
+        for (body, status, target) in cases {
+            assert_eq!(get_server_redirect(body, false), Some((status, target.to_string())),
+                       "body '{}'", body);
+        }
+
+        for body in ["rewrite ^/old/(.*)$ https://example.org/$1 permanent;",
+                     "rewrite ^/$ /index.php last;\nreturn 301 https://example.org;",
+                     "rewrite ^/$ /index.php break;\nreturn 301 https://example.org;"] {
+            assert_eq!(get_server_redirect(body, false), None, "body '{}'", body);
+        }
+    }
+
+    #[test]
+    fn internal_rewrite_without_flag_should_continue_processing() {
+        let cases = [
+            ("rewrite ^/old /new;\nreturn 301 https://example.org;", 301, "https://example.org"),
+            ("rewrite ^/$ /index.html;\nreturn 301 https://example.org;", 301, "https://example.org"),
+            ("rewrite ^/$ /home;\nrewrite ^/home$ https://example.org/home/ permanent;", 301, "https://example.org/home/"),
+        ];
+
+        for (body, status, target) in cases {
+            assert_eq!(get_server_redirect(body, false), Some((status, target.to_string())),
+                       "body '{}'", body);
+        }
+
+        let body = "rewrite ^/$ /home;\nrewrite ^/$ https://example.org permanent;";
+        assert_eq!(get_server_redirect(body, false), None);
+    }
+
+    #[test]
+    fn if_conditions_should_be_evaluated_for_vhost() {
+        let certbot = "if ($host = www.example.com) {\n    return 301 https://$host$request_uri;\n}\n\
+                       if ($host = example.com) {\n    return 301 https://$host$request_uri;\n}\n\
+                       listen 80;\nreturn 404;";
+
+        assert_eq!(get_server_redirect(certbot, false), Some((301, "https://example.com/".to_string())));
+
+        let other_host = "if ($host = www.example.com) {\n    return 301 https://$host$request_uri;\n}\n\
+                          return 404;";
+
+        assert_eq!(get_server_redirect(other_host, false), None);
+
+        let scheme = "if ($scheme != \"https\") {\n    return 301 https://$host$request_uri;\n}";
+
+        assert_eq!(get_server_redirect(scheme, false), Some((301, "https://example.com/".to_string())));
+        assert_eq!(get_server_redirect(scheme, true), None);
+
+        let regex = "if ($host ~* ^(example\\.com)$) {\n    return 301 https://www.$1$request_uri;\n}";
+
+        assert_eq!(get_server_redirect(regex, false),
+                   Some((301, "https://www.example.com/".to_string())));
+
+        let unsupported = "if ($http_user_agent ~ bot) {\n    return 301 https://example.org;\n}\n\
+                           if ($request_method = POST) {\n    return 301 https://example.org;\n}";
+
+        assert_eq!(get_server_redirect(unsupported, false), None);
+    }
+}
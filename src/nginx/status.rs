@@ -0,0 +1,124 @@
+use std::iter::once;
+
+use crate::domain::VirtualHost;
+use crate::nginx::LOCATION_DIRECTIVE;
+use crate::nginx::parser::Directive;
+use crate::nginx::redirect::get_return_status;
+
+const AUTH_BASIC_DIRECTIVE: &str = "auth_basic";
+const AUTH_BASIC_OFF_VALUE: &str = "off";
+
+const ALLOW_DIRECTIVE: &str = "allow";
+const DENY_DIRECTIVE: &str = "deny";
+const ALL_ADDRESSES_VALUE: &str = "all";
+
+const UNAUTHORIZED_STATUS: u16 = 401;
+const FORBIDDEN_STATUS: u16 = 403;
+
+/// Expected status of request to `/` without credentials, in nginx phases order:
+/// `return` of server block, `return` of root location, `deny all`, `auth_basic`.
+/// `None` if the response status can't be inferred (e.g. it's served by the location content).
+pub fn get_expected_status(server: &Directive, vhost: &VirtualHost) -> Option<u16> {
+    if let Some(status) = get_return_status(server.children(), vhost) {
+        return Some(status);
+    }
+
+    let location = get_root_location(server);
+
+    if let Some(status) = location.and_then(|location| get_return_status(location.children(), vhost)) {
+        return Some(status);
+    }
+
+    // the most specific block first
+    let blocks: Vec<&Directive> = location.into_iter().chain(once(server)).collect();
+
+    if is_access_denied(&blocks) {
+        Some(FORBIDDEN_STATUS)
+
+    } else if is_auth_basic_enabled(&blocks) {
+        Some(UNAUTHORIZED_STATUS)
+
+    } else {
+        None
+    }
+}
+
+/// `location = /`, otherwise `location /` or `location ^~ /`. Regex locations aren't supported.
+fn get_root_location(server: &Directive) -> Option<&Directive> {
+    let locations: Vec<&Directive> = server.children().iter()
+        .filter(|directive| directive.name == LOCATION_DIRECTIVE)
+        .collect();
+
+    let exact_location = locations.iter().find(|location| {
+        matches!(location.args.as_slice(), [modifier, path] if modifier == "=" && path == "/")
+    });
+
+    exact_location
+        .or_else(|| locations.iter().find(|location| match location.args.as_slice() {
+            [path] => path == "/",
+            [modifier, path] => modifier == "^~" && path == "/",
+            _ => false
+        }))
+        .copied()
+}
+
+/// Access rules are inherited from server block if location doesn't have its own.
+/// Only `deny all` as the first rule denies any client, other rules depend on client address.
+fn is_access_denied(blocks: &[&Directive]) -> bool {
+    blocks.iter()
+        .map(|block| block.children().iter()
+            .filter(|directive| directive.name == ALLOW_DIRECTIVE || directive.name == DENY_DIRECTIVE)
+            .collect::<Vec<&Directive>>())
+        .find(|rules| !rules.is_empty())
+        .and_then(|rules| rules.first().copied())
+        .is_some_and(|rule| {
+            rule.name == DENY_DIRECTIVE && rule.args.first().is_some_and(|value| value == ALL_ADDRESSES_VALUE)
+        })
+}
+
+/// `auth_basic "Restricted";` of location or server block, `auth_basic off;` disables it.
+fn is_auth_basic_enabled(blocks: &[&Directive]) -> bool {
+    blocks.iter()
+        .find_map(|block| block.children().iter().find(|directive| directive.name == AUTH_BASIC_DIRECTIVE))
+        .and_then(|directive| directive.args.first())
+        .is_some_and(|realm| realm != AUTH_BASIC_OFF_VALUE)
+}
+
+#[cfg(test)]
+mod status_tests {
+    use crate::domain::VirtualHost;
+    use crate::nginx::parser::parse_config;
+    use crate::nginx::status::get_expected_status;
+
+    fn get_server_status(server_body: &str) -> Option<u16> {
+        let server = &parse_config(&format!("server {{\n{server_body}\n}}")).unwrap()[0];
+
+        let vhost = VirtualHost { domain: "example.com".to_string(), port: 80, ..Default::default() };
+
+        get_expected_status(server, &vhost)
+    }
+
+    #[test]
+    fn expected_status_should_be_inferred_from_return_and_access_directives() {
+        let cases = [
+            ("return 404;", Some(404)),
+            ("return 200 'ok';", Some(200)),
+            ("return 444;", None),
+            ("if ($host = example.org) { return 301 https://example.org; }\nreturn 404;", Some(404)),
+            ("location / { return 410; }", Some(410)),
+            ("location / { root /var/www; }\nlocation = / { return 403; }", Some(403)),
+            ("location /api/ { return 404; }", None),
+            ("auth_basic \"Restricted\";", Some(401)),
+            ("auth_basic \"Restricted\";\nlocation / { auth_basic off; }", None),
+            ("location / { auth_basic \"Admin\"; }", Some(401)),
+            ("deny all;\nauth_basic \"Restricted\";", Some(403)),
+            ("allow 10.0.0.0/8;\ndeny all;", None),
+            ("deny all;\nlocation / { allow all; }", None),
+            ("location / { try_files $uri $uri/ =404; }", None),
+        ];
+
+        for (server_body, expected_status) in cases {
+            assert_eq!(get_server_status(server_body), expected_status, "'{}'", server_body);
+        }
+    }
+}
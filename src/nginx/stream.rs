@@ -0,0 +1,105 @@
+use crate::domain::StreamServer;
+use crate::nginx::{get_listen, LISTEN_DIRECTIVE, SERVER_DIRECTIVE};
+use crate::nginx::parser::Directive;
+
+const STREAM_DIRECTIVE: &str = "stream";
+const PROXY_PASS_DIRECTIVE: &str = "proxy_pass";
+
+/// Returns stream servers from `stream` blocks and top-level stream servers
+/// (files included into `stream` context, e.g. `/etc/nginx/streams-enabled/*`).
+pub fn get_stream_servers(directives: &[Directive]) -> Vec<StreamServer> {
+    get_stream_server_blocks(directives).into_iter()
+        .flat_map(get_stream_servers_from_server_block)
+        .collect()
+}
+
+fn get_stream_server_blocks(directives: &[Directive]) -> Vec<&Directive> {
+    let mut server_blocks: Vec<&Directive> = Vec::new();
+
+    for directive in directives {
+        if directive.name == STREAM_DIRECTIVE && directive.is_block() {
+            server_blocks.extend(directive.children().iter().filter(
+                |child| child.name == SERVER_DIRECTIVE && child.is_block()));
+
+        } else if directive.name == SERVER_DIRECTIVE && is_stream_server(directive) {
+            server_blocks.push(directive);
+        }
+    }
+
+    server_blocks
+}
+
+/// Server block outside of `stream` block is a stream server if it has `proxy_pass`
+/// (allowed inside `location` only for HTTP) or UDP listen.
+pub fn is_stream_server(server: &Directive) -> bool {
+    server.children().iter().any(|directive| {
+        directive.name == PROXY_PASS_DIRECTIVE ||
+            (directive.name == LISTEN_DIRECTIVE && get_listen(directive).is_some_and(|listen| listen.udp))
+    })
+}
+
+/// Returns stream server for every `listen` address and port.
+fn get_stream_servers_from_server_block(server: &Directive) -> Vec<StreamServer> {
+    trace!("stream server block at line {}", server.line);
+
+    let upstream = server.children().iter()
+        .find(|directive| directive.name == PROXY_PASS_DIRECTIVE)
+        .and_then(|directive| directive.args.first())
+        .map(|upstream| upstream.to_string());
+
+    let mut stream_servers: Vec<StreamServer> = Vec::new();
+
+    let listens = server.children().iter()
+        .filter(|directive| directive.name == LISTEN_DIRECTIVE)
+        .filter_map(get_listen);
+
+    for listen in listens {
+        let stream_server = StreamServer {
+            port: listen.port, udp: listen.udp, address: listen.address, upstream: upstream.clone()
+        };
+
+        if !stream_servers.contains(&stream_server) {
+            debug!("stream server found {:?}", stream_server);
+            stream_servers.push(stream_server);
+        }
+    }
+
+    stream_servers
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use crate::domain::StreamServer;
+    use crate::nginx::parser::parse_config;
+    use crate::nginx::stream::get_stream_servers;
+
+    #[test]
+    fn stream_servers_should_be_collected_from_stream_blocks() {
+        let directives = parse_config("stream {\n    upstream db {\n        server 10.0.0.5:5432;\n    }\n\
+                                       server {\n        listen 5432;\n        proxy_pass db;\n    }\n\
+                                       server {\n        listen 10.0.0.1:53 udp;\n        \
+                                       proxy_pass 10.0.0.2:53;\n    }\n}\n\
+                                       http {\n    server {\n        listen 80;\n    }\n}").unwrap();
+
+        assert_eq!(get_stream_servers(&directives), vec![
+            StreamServer { port: 5432, udp: false, address: None, upstream: Some("db".to_string()) },
+            StreamServer {
+                port: 53, udp: true, address: Some("10.0.0.1".to_string()),
+                upstream: Some("10.0.0.2:53".to_string())
+            },
+        ]);
+    }
+
+    #[test]
+    fn top_level_stream_servers_should_be_detected() {
+        let directives = parse_config("server {\n    listen 6379;\n    proxy_pass redis;\n}\n\
+                                       server {\n    listen 514 udp;\n}\n\
+                                       server {\n    listen 80;\n    server_name example.com;\n    \
+                                       location / {\n        proxy_pass http://backend;\n    }\n}").unwrap();
+
+        let ports: Vec<(i32, bool)> = get_stream_servers(&directives).iter()
+            .map(|stream_server| (stream_server.port, stream_server.udp)).collect();
+
+        assert_eq!(ports, vec![(6379, false), (514, true)]);
+    }
+}
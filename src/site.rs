@@ -1,56 +1,130 @@
 use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT, WWW_SEARCH_PATTERN};
 use crate::domain::{Site, VirtualHost};
 
-pub fn get_domains_from_vhosts(vhosts: Vec<VirtualHost>,
-                               include_domains_with_www: bool) -> Vec<Site> {
+/// Forms of international domain names (`пример.рф`) in `{#NAME}` and `{#URL}`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdnFormat {
+    /// Unicode name, punycode url: `пример.рф`, `https://xn--e1afmkfd.xn--p1ai`
+    UnicodeName,
+
+    Punycode,
+
+    Unicode
+}
+
+pub fn get_domains_from_vhosts(vhosts: Vec<VirtualHost>, include_domains_with_www: bool,
+                               idn_format: IdnFormat) -> Vec<Site> {
     let sites: Vec<Site> = vhosts.iter()
         .filter(|vhost| {
             let domain_in_lowercase = vhost.domain.to_lowercase();
 
             let domain_starts_with_www = domain_in_lowercase.starts_with(WWW_SEARCH_PATTERN);
 
-            (!include_domains_with_www && !domain_starts_with_www) ||
-                (include_domains_with_www && domain_starts_with_www) ||
-                !domain_starts_with_www
+            include_domains_with_www || !domain_starts_with_www
+
+        }).flat_map(|vhost| get_sites_from_vhost(vhost, idn_format)).collect();
+
+    sites
+}
+
+/// Port 443 is treated as HTTPS even without explicit TLS settings in vhost config.
+pub fn get_url(domain: &str, vhost_port: i32, tls: bool) -> String {
+    match (vhost_port, tls) {
+        (DEFAULT_HTTPS_PORT, _) => format!("https://{domain}"),
+        (DEFAULT_HTTP_PORT, false) => format!("http://{domain}"),
+        (_, true) => format!("https://{domain}:{vhost_port}"),
+        (_, false) => format!("http://{domain}:{vhost_port}")
+    }
+}
+
+/// Site for every monitoring path of vhost, site without path if vhost doesn't have them.
+/// Path is appended to `{#NAME}` and `{#URL}` to keep items unique: `example.com/healthz`.
+fn get_sites_from_vhost(vhost: &VirtualHost, idn_format: IdnFormat) -> Vec<Site> {
+    let site = get_domain_from_vhost(vhost, idn_format);
+
+    if vhost.paths.is_empty() {
+        return vec![site];
+    }
 
-        }).map(get_domain_from_vhost).collect();
+    vhost.paths.iter()
+        .map(|path| Site {
+            name: format!("{}{path}", site.name), url: format!("{}{path}", site.url),
+            path: Some(path.to_string()), ..site.clone()
+        })
+        .collect()
+}
 
-    return sites;
+fn get_domain_from_vhost(vhost: &VirtualHost, idn_format: IdnFormat) -> Site {
+    let (name_domain, url_domain) = match idn_format {
+        IdnFormat::UnicodeName => (to_unicode(&vhost.domain), to_punycode(&vhost.domain)),
+        IdnFormat::Punycode => (to_punycode(&vhost.domain), to_punycode(&vhost.domain)),
+        IdnFormat::Unicode => (to_unicode(&vhost.domain), to_unicode(&vhost.domain))
+    };
+
+    let url = get_url(&url_domain, vhost.port, vhost.tls);
+    let name = get_site_name(&name_domain, vhost.port, vhost.tls);
+
+    // certificate is used only by HTTPS listen of the server
+    let certificate = vhost.certificate.as_ref()
+        .filter(|_| vhost.tls || vhost.port == DEFAULT_HTTPS_PORT);
+
+    Site {
+        name, url, path: None, address: vhost.address.clone(),
+        redirect_to: vhost.redirect.as_ref().map(|redirect| redirect.target.to_string()),
+        expected_status: vhost.expected_status
+            .or_else(|| vhost.redirect.as_ref().map(|redirect| redirect.status)),
+        display_name: vhost.display_name.clone(),
+        tags: if vhost.tags.is_empty() { None } else { Some(vhost.tags.join(",")) },
+        cert_path: certificate.map(|certificate| certificate.path.to_string()),
+        cert_not_after: certificate.and_then(|certificate| certificate.not_after),
+        cert_sans: certificate.filter(|certificate| !certificate.sans.is_empty())
+            .map(|certificate| certificate.sans.join(","))
+    }
 }
 
-pub fn get_url(domain: &str, vhost_port: i32) -> String {
-    match vhost_port {
-        DEFAULT_HTTP_PORT => String::from(format!("http://{domain}")),
-        DEFAULT_HTTPS_PORT => String::from(format!("https://{domain}")),
-        _ => String::from(format!("http://{domain}:{vhost_port}"))
+/// `пример.рф` -> `xn--e1afmkfd.xn--p1ai`, ASCII names are kept as is
+fn to_punycode(domain: &str) -> String {
+    if domain.is_ascii() {
+        return domain.to_string();
     }
+
+    idna::domain_to_ascii(domain).unwrap_or_else(|_| {
+        warn!("couldn't convert domain '{domain}' to punycode");
+        domain.to_string()
+    })
 }
 
-fn get_domain_from_vhost(vhost: &VirtualHost) -> Site {
-    let url = get_url(&vhost.domain, vhost.port);
-    Site { name: get_site_name(&vhost.domain, vhost.port), url }
+fn to_unicode(domain: &str) -> String {
+    if !domain.contains("xn--") {
+        return domain.to_string();
+    }
+
+    match idna::domain_to_unicode(domain) {
+        (unicode_domain, Ok(())) => unicode_domain,
+        _ => domain.to_string()
+    }
 }
 
-fn get_site_name(domain: &str, port: i32) -> String {
-    match port {
-        DEFAULT_HTTP_PORT => String::from(format!("{domain}_http")),
-        DEFAULT_HTTPS_PORT => String::from(domain),
-        _ => String::from(format!("{domain}:{port}"))
+fn get_site_name(domain: &str, port: i32, tls: bool) -> String {
+    match (port, tls) {
+        (DEFAULT_HTTP_PORT, false) => format!("{domain}_http"),
+        (DEFAULT_HTTPS_PORT, _) => String::from(domain),
+        _ => format!("{domain}:{port}")
     }
 }
 
 #[cfg(test)]
 mod site_tests {
     use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
-    use crate::domain::{Site, VirtualHost};
-    use crate::site::get_domains_from_vhosts;
+    use crate::domain::{Certificate, Site, VirtualHost};
+    use crate::site::{get_domains_from_vhosts, IdnFormat};
     use crate::test_utils::samples::{get_4_sample_vhosts, SAMPLE_DOMAIN1, SAMPLE_DOMAIN2, SAMPLE_DOMAIN3, SAMPLE_DOMAIN4};
 
     #[test]
     fn without_www_domains_result_should_not_contain_domains_with_www_lol() {
         let vhosts = get_4_sample_vhosts();
 
-        let results = get_domains_from_vhosts(vhosts, false);
+        let results = get_domains_from_vhosts(vhosts, false, IdnFormat::UnicodeName);
 
         assert_eq!(results.len(), 3);
 
@@ -64,7 +138,7 @@ mod site_tests {
     fn with_www_domains_results_should_contain_domains_with_www() {
         let vhosts = get_4_sample_vhosts();
 
-        let results = get_domains_from_vhosts(vhosts, true);
+        let results = get_domains_from_vhosts(vhosts, true, IdnFormat::UnicodeName);
 
         assert_eq!(results.len(), 4);
 
@@ -74,10 +148,12 @@ mod site_tests {
 
     #[test]
     fn vhost_with_https_port_should_contain_https_prefix_for_url() {
-        let vhost1 = VirtualHost { domain: SAMPLE_DOMAIN2.to_string(), port: DEFAULT_HTTPS_PORT };
+        let vhost1 = VirtualHost {
+            domain: SAMPLE_DOMAIN2.to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default()
+        };
         let vhosts = vec![vhost1.clone()];
 
-        let results = get_domains_from_vhosts(vhosts, false);
+        let results = get_domains_from_vhosts(vhosts, false, IdnFormat::UnicodeName);
 
         assert_eq!(results.len(), 1);
 
@@ -88,10 +164,12 @@ mod site_tests {
 
     #[test]
     fn vhost_with_standard_http_port_should_contain_http_prefix_for_url() {
-        let vhost1 = VirtualHost { domain: SAMPLE_DOMAIN3.to_string(), port: DEFAULT_HTTP_PORT };
+        let vhost1 = VirtualHost {
+            domain: SAMPLE_DOMAIN3.to_string(), port: DEFAULT_HTTP_PORT, ..Default::default()
+        };
         let vhosts = vec![vhost1.clone()];
 
-        let results = get_domains_from_vhosts(vhosts, false);
+        let results = get_domains_from_vhosts(vhosts, false, IdnFormat::UnicodeName);
 
         assert_eq!(results.len(), 1);
 
@@ -104,10 +182,12 @@ mod site_tests {
     fn vhost_with_non_standard_port_should_contain_http_prefix_for_url() {
         let domain = SAMPLE_DOMAIN1;
         let custom_port = 2345;
-        let vhost1 = VirtualHost { domain: domain.to_string(), port: custom_port };
+        let vhost1 = VirtualHost {
+            domain: domain.to_string(), port: custom_port, ..Default::default()
+        };
         let vhosts = vec![vhost1.clone()];
 
-        let results = get_domains_from_vhosts(vhosts, false);
+        let results = get_domains_from_vhosts(vhosts, false, IdnFormat::UnicodeName);
 
         assert_eq!(results.len(), 1);
 
@@ -118,10 +198,12 @@ mod site_tests {
 
     #[test]
     fn site_name_without_https_should_contain_http_postfix() {
-        let vhost1 = VirtualHost { domain: SAMPLE_DOMAIN3.to_string(), port: DEFAULT_HTTP_PORT };
+        let vhost1 = VirtualHost {
+            domain: SAMPLE_DOMAIN3.to_string(), port: DEFAULT_HTTP_PORT, ..Default::default()
+        };
         let vhosts = vec![vhost1.clone()];
 
-        let results = get_domains_from_vhosts(vhosts, false);
+        let results = get_domains_from_vhosts(vhosts, false, IdnFormat::UnicodeName);
 
         assert_eq!(results.len(), 1);
 
@@ -131,8 +213,115 @@ mod site_tests {
         assert!(site_found.is_some())
     }
 
-    fn assert_site_with_url(sites: &Vec<Site>, url: &str) {
+    #[test]
+    fn vhost_with_tls_on_non_standard_port_should_contain_https_prefix_for_url() {
+        let vhost1 = VirtualHost {
+            domain: SAMPLE_DOMAIN1.to_string(), port: 8443, tls: true, ..Default::default()
+        };
+
+        let results = get_domains_from_vhosts(vec![vhost1], false, IdnFormat::UnicodeName);
+
+        assert_eq!(results.len(), 1);
+
+        let site = results.first().unwrap();
+        assert_eq!(site.url, format!("https://{SAMPLE_DOMAIN1}:8443"));
+        assert_eq!(site.name, format!("{SAMPLE_DOMAIN1}:8443"));
+    }
+
+    #[test]
+    fn vhost_with_tls_on_http_port_should_contain_port_in_url() {
+        let vhost1 = VirtualHost {
+            domain: SAMPLE_DOMAIN1.to_string(), port: DEFAULT_HTTP_PORT, tls: true,
+            ..Default::default()
+        };
+
+        let results = get_domains_from_vhosts(vec![vhost1], false, IdnFormat::UnicodeName);
+
+        let site = results.first().unwrap();
+        assert_eq!(site.url, format!("https://{SAMPLE_DOMAIN1}:80"));
+        assert_eq!(site.name, format!("{SAMPLE_DOMAIN1}:80"));
+    }
+
+    fn assert_site_with_url(sites: &[Site], url: &str) {
         let site_found = sites.iter().find(|site| site.url == url);
         assert!(site_found.is_some())
     }
+
+    #[test]
+    fn international_domain_should_be_converted_by_idn_format() {
+        let vhost = VirtualHost { domain: "пример.рф".to_string(), port: DEFAULT_HTTPS_PORT, ..Default::default() };
+
+        let cases = [
+            (IdnFormat::UnicodeName, "пример.рф", "https://xn--e1afmkfd.xn--p1ai"),
+            (IdnFormat::Punycode, "xn--e1afmkfd.xn--p1ai", "https://xn--e1afmkfd.xn--p1ai"),
+            (IdnFormat::Unicode, "пример.рф", "https://пример.рф"),
+        ];
+
+        for (idn_format, name, url) in cases {
+            let sites = get_domains_from_vhosts(vec![vhost.clone()], false, idn_format);
+
+            assert_eq!(sites[0].name, name);
+            assert_eq!(sites[0].url, url);
+        }
+    }
+
+    #[test]
+    fn certificate_should_be_added_to_https_sites_only() {
+        let certificate = Certificate {
+            path: "/etc/ssl/example.pem".to_string(), not_after: Some(2051222400),
+            sans: vec!["example.com".to_string(), "*.example.com".to_string()],
+            ..Default::default()
+        };
+
+        let vhosts = vec![
+            VirtualHost {
+                domain: SAMPLE_DOMAIN1.to_string(), port: DEFAULT_HTTPS_PORT,
+                certificate: Some(certificate.clone()), ..Default::default()
+            },
+            VirtualHost {
+                domain: SAMPLE_DOMAIN1.to_string(), port: DEFAULT_HTTP_PORT,
+                certificate: Some(certificate), ..Default::default()
+            },
+        ];
+
+        let sites = get_domains_from_vhosts(vhosts, false, IdnFormat::UnicodeName);
+
+        assert_eq!(sites[0].cert_path.as_deref(), Some("/etc/ssl/example.pem"));
+        assert_eq!(sites[0].cert_not_after, Some(2051222400));
+        assert_eq!(sites[0].cert_sans.as_deref(), Some("example.com,*.example.com"));
+
+        assert!(sites[1].cert_path.is_none());
+    }
+
+    #[test]
+    fn site_should_be_added_for_every_monitoring_path() {
+        let vhost = VirtualHost {
+            domain: SAMPLE_DOMAIN1.to_string(), port: DEFAULT_HTTPS_PORT,
+            paths: vec!["/healthz".to_string(), "/api/status".to_string()], ..Default::default()
+        };
+
+        let sites = get_domains_from_vhosts(vec![vhost], false, IdnFormat::UnicodeName);
+
+        let paths: Vec<(String, String, Option<&str>)> = sites.iter()
+            .map(|site| (site.name.to_string(), site.url.to_string(), site.path.as_deref())).collect();
+
+        assert_eq!(paths, vec![
+            (format!("{SAMPLE_DOMAIN1}/healthz"), format!("https://{SAMPLE_DOMAIN1}/healthz"), Some("/healthz")),
+            (format!("{SAMPLE_DOMAIN1}/api/status"), format!("https://{SAMPLE_DOMAIN1}/api/status"), Some("/api/status"))
+        ]);
+    }
+
+    #[test]
+    fn sites_of_monitoring_paths_should_have_unique_names() {
+        let vhost = VirtualHost {
+            domain: SAMPLE_DOMAIN1.to_string(), port: 8080,
+            paths: vec!["/healthz".to_string(), "/ping".to_string()], ..Default::default()
+        };
+
+        let sites = get_domains_from_vhosts(vec![vhost], false, IdnFormat::UnicodeName);
+
+        let names: Vec<&str> = sites.iter().map(|site| site.name.as_str()).collect();
+
+        assert_eq!(names, vec![format!("{SAMPLE_DOMAIN1}:8080/healthz"), format!("{SAMPLE_DOMAIN1}:8080/ping")]);
+    }
 }
@@ -0,0 +1,55 @@
+use crate::domain::{Stream, StreamServer};
+
+const TCP_PROTO: &str = "tcp";
+const UDP_PROTO: &str = "udp";
+
+/// Stream for every address, port and protocol, duplicates are skipped.
+pub fn get_streams_from_stream_servers(stream_servers: &[StreamServer]) -> Vec<Stream> {
+    let mut streams: Vec<Stream> = Vec::new();
+
+    for stream_server in stream_servers {
+        let proto = if stream_server.udp { UDP_PROTO } else { TCP_PROTO };
+
+        let duplicate = streams.iter().any(|stream| {
+            stream.port == stream_server.port && stream.proto == proto &&
+                stream.address == stream_server.address
+        });
+
+        if duplicate {
+            debug!("stream server with port {} ({proto}) is already added, skip", stream_server.port);
+            continue;
+        }
+
+        streams.push(Stream {
+            port: stream_server.port,
+            proto: proto.to_string(),
+            address: stream_server.address.clone(),
+            upstream: stream_server.upstream.clone()
+        });
+    }
+
+    streams
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use crate::domain::StreamServer;
+    use crate::stream::get_streams_from_stream_servers;
+
+    #[test]
+    fn streams_should_be_unique_by_address_port_and_proto() {
+        let stream_servers = vec![
+            StreamServer { port: 53, udp: true, upstream: Some("dns".to_string()), ..Default::default() },
+            StreamServer { port: 53, udp: false, upstream: Some("dns".to_string()), ..Default::default() },
+            StreamServer { port: 53, udp: true, upstream: Some("dns2".to_string()), ..Default::default() },
+        ];
+
+        let streams: Vec<(i32, String, Option<String>)> = get_streams_from_stream_servers(&stream_servers)
+            .into_iter().map(|stream| (stream.port, stream.proto, stream.upstream)).collect();
+
+        assert_eq!(streams, vec![
+            (53, "udp".to_string(), Some("dns".to_string())),
+            (53, "tcp".to_string(), Some("dns".to_string())),
+        ]);
+    }
+}
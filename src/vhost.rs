@@ -1,26 +1,19 @@
-use regex::Regex;
+pub enum WebServer {
+    /// See `nginx::parser`
+    Nginx,
 
-pub struct VhostDiscoveryConfig {
-    /// Pattern for vhost section start
-    ///
-    /// Example for nginx:
-    ///
-    /// ```nginx
-    /// server {
-    /// ```
-    pub section_start: Regex,
-
-    /// Pattern for redirect to another url
-    pub redirect_to_url: Regex,
-
-    /// Pattern for vhost port
-    pub port: Regex,
+    /// See `apache::parser`
+    Apache
+}
 
-    /// Pattern for vhost domain
-    pub domain: Regex,
+pub struct VhostDiscoveryConfig {
+    pub web_server: WebServer,
 
     /// Scan sub-directories for vhost files
     pub include_subdirs: bool,
 
-    pub file_extensions: Vec<String>
-}
\ No newline at end of file
+    pub file_extensions: Vec<String>,
+
+    /// Loaded modules for apache `<IfModule>` evaluation, empty if unknown
+    pub modules: Vec<String>
+}
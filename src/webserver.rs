@@ -1,19 +1,33 @@
 use std::{fs, io};
-use std::fs::{DirEntry, File};
-use std::io::{BufRead, BufReader};
+use std::fmt::Debug;
+use std::fs::DirEntry;
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context};
-use regex::Regex;
 
+use crate::{apache, nginx};
 use crate::domain::VirtualHost;
-use crate::vhost::VhostDiscoveryConfig;
+use crate::vhost::{VhostDiscoveryConfig, WebServer};
+
+const APACHE_DEFAULT_ADDRESS: &str = "_default_";
 
 pub fn get_vhosts(path: &Path, config: &VhostDiscoveryConfig,
                   fall_on_parse_errors: bool) -> anyhow::Result<Vec<VirtualHost>> {
+    get_from_config_files(path, config, fall_on_parse_errors, |vhost_file| {
+        match &config.web_server {
+            WebServer::Nginx => nginx::get_virtual_hosts_from_file(vhost_file),
+            WebServer::Apache => apache::get_virtual_hosts_from_file(vhost_file, &config.modules)
+        }
+    })
+}
+
+/// Collects items (vhosts, stream servers, etc.) from every config file of the path.
+pub fn get_from_config_files<T: Debug>(path: &Path, config: &VhostDiscoveryConfig, fall_on_parse_errors: bool,
+                                       get_from_file: impl Fn(&Path) -> anyhow::Result<Vec<T>>)
+                                       -> anyhow::Result<Vec<T>> {
     info!("getting vhosts from path '{}'..", path.display());
 
-    let mut results: Vec<VirtualHost> = Vec::new();
+    let mut results: Vec<T> = Vec::new();
 
     if path.is_dir() && path.exists() {
         let vhost_files = get_vhost_config_file_list(
@@ -25,15 +39,11 @@ pub fn get_vhosts(path: &Path, config: &VhostDiscoveryConfig,
 
             debug!("processing file '{}'", vhost_file_path.display());
 
-            match get_virtual_hosts_from_file(
-                vhost_file_path,
-                &config.section_start, &config.redirect_to_url,
-                &config.port, &config.domain
-            ) {
-                Ok(vhosts) => {
-                    for vhost in vhosts {
-                        debug!("{}", vhost.to_string());
-                        results.push(vhost);
+            match get_from_file(vhost_file_path) {
+                Ok(items) => {
+                    for item in items {
+                        debug!("{:?}", item);
+                        results.push(item);
                     }
                 }
                 Err(e) => {
@@ -57,10 +67,10 @@ pub fn get_vhosts(path: &Path, config: &VhostDiscoveryConfig,
 
 }
 
-pub fn get_vhost_config_file_list(vhost_root_path: &Path, file_extensions: &Vec<String>,
+pub fn get_vhost_config_file_list(vhost_root_path: &Path, file_extensions: &[String],
                                   recursive: bool) -> Result<Vec<PathBuf>,io::Error> {
 
-    let paths = fs::read_dir(&vhost_root_path)?;
+    let paths = fs::read_dir(vhost_root_path)?;
 
     let mut vhost_files: Vec<PathBuf> = Vec::new();
 
@@ -73,16 +83,16 @@ pub fn get_vhost_config_file_list(vhost_root_path: &Path, file_extensions: &Vec<
                     let path_entry = dir_entry.path();
                     let path_subdir_entry = path_entry.as_path();
 
-                    match get_vhost_config_file_list(path_subdir_entry, &file_extensions, recursive) {
+                    match get_vhost_config_file_list(path_subdir_entry, file_extensions, recursive) {
                         Ok(mut vhosts) => vhost_files.append(&mut vhosts),
                         Err(e) => error!("{}", e)
                     }
                 }
 
                 if file_type.is_file() || file_type.is_symlink() {
-                    match get_vhost_file_from_dir(vhost_root_path, &file_extensions, &dir_entry) {
-                        Some(file_path) => vhost_files.push(file_path),
-                        None => {}
+                    if let Some(file_path) = get_vhost_file_from_dir(
+                        vhost_root_path, file_extensions, &dir_entry) {
+                        vhost_files.push(file_path)
                     }
                 }
             }
@@ -95,160 +105,123 @@ pub fn get_vhost_config_file_list(vhost_root_path: &Path, file_extensions: &Vec<
     Ok(vhost_files)
 }
 
-pub fn get_virtual_hosts_from_file(
-    vhost_file: &Path, section_start_pattern: &Regex, redirect_with_301_pattern: &Regex,
-    port_search_pattern: &Regex,
-    domain_search_pattern: &Regex) -> anyhow::Result<Vec<VirtualHost>> {
-
-    let mut hosts: Vec<VirtualHost> = Vec::new();
-
-    let vhost_file_name = vhost_file.to_str().unwrap();
-
-    info!("get virtual hosts from file '{vhost_file_name}'");
-
-    let input = File::open(vhost_file)?;
-    let buffered = BufReader::new(input);
-
-    let mut inside_server_section = false;
-    let mut redirect_to_url = false;
-    let mut port: Option<i32> = None;
-    let mut domain: Option<String> = None;
-
-    let mut previous_row: Option<String> = None;
-
-    for line in buffered.lines() {
-        let row = line.unwrap_or(String::new());
-        trace!("row '{row}'");
+fn get_vhost_file_from_dir(vhost_root_path: &Path, file_extensions: &[String],
+                           dir_entry: &DirEntry) -> Option<PathBuf> {
+    let mut result: Option<PathBuf> = None;
 
-        if section_start_pattern.is_match(&row) {
-            if domain.is_none() && port.is_some() {
-                domain = None;
-                port = None;
-            }
+    if let Ok(file_type) = dir_entry.file_type() {
+        if file_type.is_file() || file_type.is_symlink() {
+            if let Ok(file_name) = dir_entry.file_name().into_string() {
 
-            if domain.is_some() && port.is_some() && !redirect_to_url {
-                let vhost = get_virtual_host(domain, port);
+                for file_ext in file_extensions.iter() {
 
-                hosts.push(vhost);
+                    if file_name.ends_with(file_ext) {
+                        let vhost_file = vhost_root_path.join(file_name);
+                        result = Some(vhost_file);
+                        break;
+                    }
 
-                domain = None;
-                port = None;
+                }
             }
-
-            inside_server_section = true;
-            redirect_to_url = false;
         }
+    }
 
-        if inside_server_section {
-            if redirect_with_301_pattern.is_match(&row) {
-                trace!("redirect 301 pattern has been matched");
-
-                match previous_row {
-                    Some(previous_row_value) => {
-                        trace!("previous row value: '{previous_row_value}'");
-                        if !previous_row_value.contains("location /") {
-                            debug!(
-                                    "previous row doesn't contain 'location /', \
-                                    redirect 301 was detected, skip vhost"
-                                );
-                            redirect_to_url = true;
-                            domain = None;
-                            port = None;
-                            inside_server_section = false;
-                        }
-                    }
-                    None => {
-                        debug!("redirect detected, skip vhost");
-                        redirect_to_url = true;
-                        domain = None;
-                        port = None;
-                        inside_server_section = false;
-                    }
-                }
-            }
+    result
+}
 
-            if port.is_none() && port_search_pattern.is_match(&row) {
-                trace!("port wasn't detected yet, port pattern has been matched");
-                let vhost_port_str = find_group_with_port_value(
-                    &row, &port_search_pattern
-                );
+/// Address part of nginx `listen` or apache `<VirtualHost>`.
+#[derive(Debug, PartialEq)]
+pub struct ListenAddress {
+    /// Bind address (IPv4, IPv6 without brackets or hostname),
+    /// `None` for `*`, apache `_default_` or omitted address
+    pub address: Option<String>,
 
-                trace!("vhost port: '{vhost_port_str}'");
+    pub port: Option<i32>
+}
 
-                if let Ok(vhost_port) = vhost_port_str.parse() {
-                    debug!("port found {}", vhost_port);
-                    port = Some(vhost_port);
+/// Supported formats: `80`, `*:80`, `10.0.0.1:80`, `10.0.0.1`, `localhost:80`, `[::]:80`, `[::1]`
+pub fn parse_listen_address(value: &str) -> Option<ListenAddress> {
+    if value.chars().all(|c| c.is_ascii_digit()) {
+        return Some(ListenAddress { address: None, port: Some(value.parse().ok()?) });
+    }
 
-                } else { error!("couldn't parse port value '{vhost_port_str}'"); }
-            }
+    let (address, port) = split_host_port(value)?;
 
-            if domain.is_none() && domain_search_pattern.is_match(&row) {
-                let domains_row = get_first_group_match_as_string(
-                    &row, &domain_search_pattern
-                );
-                let sanitized_domains_row = domains_row.replace(r"[\s\t]{2}", " ");
-                let domains: Vec<&str> = sanitized_domains_row.split(" ").collect::<Vec<&str>>();
-                if let Some(domain_value) = domains.first() {
-                    debug!("domain found {domain_value}");
-                    domain = Some(domain_value.to_string());
-                }
-            }
+    if value.starts_with('[') {
+        if address.is_empty() || !address.chars().all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.') {
+            return None;
         }
 
-        previous_row = Some(row)
+        return Some(ListenAddress { address: Some(address.to_string()), port })
     }
 
-    if port.is_some() && domain.is_some() && !redirect_to_url {
-        hosts.push(get_virtual_host(domain, port));
+    if address == "*" || address == APACHE_DEFAULT_ADDRESS {
+        return Some(ListenAddress { address: None, port });
     }
 
-    Ok(hosts)
-}
+    let valid_address = !address.is_empty() && address.chars().all(
+        |c| c.is_ascii_alphanumeric() || c == '.' || c == '-'
+    );
 
-fn get_vhost_file_from_dir(vhost_root_path: &Path, file_extensions: &Vec<String>,
-                           dir_entry: &DirEntry) -> Option<PathBuf> {
-    let mut result: Option<PathBuf> = None;
+    if !valid_address {
+        return None;
+    }
 
-    if let Ok(file_type) = dir_entry.file_type() {
-        if file_type.is_file() || file_type.is_symlink() {
-            if let Ok(file_name) = dir_entry.file_name().into_string() {
+    Some(ListenAddress { address: Some(address.to_string()), port })
+}
 
-                for file_ext in file_extensions.iter() {
+/// Host and optional numeric port: `example.com:80`, `example.com`, `[::1]:443`.
+/// IPv6 host is returned without brackets.
+pub fn split_host_port(value: &str) -> Option<(&str, Option<i32>)> {
+    if let Some(ipv6_value) = value.strip_prefix('[') {
+        let (host, rest) = ipv6_value.split_once(']')?;
 
-                    if file_name.ends_with(file_ext) {
-                        let vhost_file = vhost_root_path.join(file_name);
-                        result = Some(vhost_file);
-                        break;
-                    }
+        let port = match rest.strip_prefix(':') {
+            Some(port) => Some(port.parse().ok()?),
+            None if rest.is_empty() => None,
+            None => return None
+        };
 
-                }
-            }
-        }
+        return Some((host, port));
     }
 
-    result
+    match value.rsplit_once(':') {
+        Some((host, port)) => Some((host, Some(port.parse().ok()?))),
+        None => Some((value, None))
+    }
 }
 
-fn get_first_group_match_as_string(row: &str, pattern: &Regex) -> String {
-    let groups = pattern.captures_iter(&row).next().unwrap();
-    String::from(&groups[1])
-}
+/// Lowercase Unicode form of the name: `Example.COM` -> `example.com`,
+/// `xn--e1afmkfd.xn--p1ai` -> `пример.рф`. Wildcard labels (`*`, `?`) are only lowercased.
+///
+/// Returns `None` for invalid IDN labels, name characters must be checked by caller.
+pub fn normalize_domain_name(name: &str) -> Option<String> {
+    let mut labels: Vec<String> = Vec::new();
+
+    for label in name.split('.') {
+        if label.contains(['*', '?']) {
+            labels.push(label.to_lowercase());
+            continue;
+        }
+
+        let (unicode_label, result) = idna::domain_to_unicode(label);
 
-fn find_group_with_port_value(row: &str, pattern: &Regex) -> String {
-    let mut port = String::new();
+        if result.is_err() {
+            debug!("invalid international domain name '{name}'");
+            return None;
+        }
 
-    for caps in pattern.captures_iter(&row) {
-        port = format!("{}", &caps["port"]);
+        labels.push(unicode_label);
     }
 
-    return port
+    Some(labels.join("."))
 }
 
-fn get_virtual_host(domain: Option<String>, port: Option<i32>) -> VirtualHost {
-    let domain_name = domain.unwrap();
-    VirtualHost {
-        domain: domain_name.to_owned(), port: port.unwrap()
-    }
+/// Normalized name: lowercase letters (including international), digits, `.` and `-`
+pub fn is_valid_domain_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(
+        |c| (c.is_alphanumeric() && !c.is_uppercase()) || c == '.' || c == '-'
+    )
 }
 
 #[cfg(test)]
@@ -263,11 +236,11 @@ mod get_vhosts_tests {
     fn vhosts_should_be_extracted_from_multiply_files_from_path() {
         let nginx_vhost_path = Path::new("test-data/nginx-multi-files");
         let config = get_nginx_discovery_config(
-            false, &vec![".conf".to_string()]);
+            false, &[".conf".to_string()]);
 
-        let vhosts = get_vhosts(&nginx_vhost_path, &config, false).unwrap();
+        let vhosts = get_vhosts(nginx_vhost_path, &config, false).unwrap();
 
-        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+        vhosts.iter().for_each(|vhost| println!("{}", vhost));
 
         println!("{:?}", vhosts);
 
@@ -282,7 +255,7 @@ mod get_vhosts_tests {
 
     #[test]
     fn return_error_for_invalid_path() {
-        let config = get_nginx_discovery_config(true, &vec![".conf".to_string()]);
+        let config = get_nginx_discovery_config(true, &[".conf".to_string()]);
         let path = Path::new("does-not-exist");
         assert!(get_vhosts(path, &config, false).is_err())
     }
@@ -292,13 +265,14 @@ mod get_vhosts_tests {
 mod webserver_tests {
     use std::path::Path;
 
-    use crate::nginx::{get_domain_search_regex_for_nginx_vhost, get_nginx_redirect_with_301_regex, get_nginx_vhost_port_regex, get_nginx_vhost_section_start_regex};
-    use crate::webserver::{get_vhost_config_file_list, get_virtual_hosts_from_file};
+    use crate::nginx::get_virtual_hosts_from_file;
+    use crate::webserver::{get_vhost_config_file_list, ListenAddress, normalize_domain_name, parse_listen_address,
+                           split_host_port};
 
     #[test]
     fn support_recursive_mode() {
         let vhost_root_path = Path::new("tests/nginx-multi-files");
-        let files = get_vhost_config_file_list(vhost_root_path,  &vec![".conf".to_string()], true).unwrap();
+        let files = get_vhost_config_file_list(vhost_root_path,  &[".conf".to_string()], true).unwrap();
 
         let expected_size: usize = 3;
         assert_eq!(&files.len(), &expected_size);
@@ -307,7 +281,7 @@ mod webserver_tests {
     #[test]
     fn get_vhost_config_file_list_should_return_file_names() {
         let vhost_root_path = Path::new("tests/apache-vhosts");
-        let files = get_vhost_config_file_list(vhost_root_path, &vec![".conf".to_string()], false).unwrap();
+        let files = get_vhost_config_file_list(vhost_root_path, &[".conf".to_string()], false).unwrap();
 
         let expected_size: usize = 2;
         assert_eq!(&files.len(), &expected_size);
@@ -316,18 +290,13 @@ mod webserver_tests {
     #[test]
     fn get_vhost_config_file_list_should_return_error_for_unknown_path() {
         let unknown_path = Path::new("unknown-path");
-        assert!(get_vhost_config_file_list(unknown_path, &vec![".conf".to_string()], false).is_err());
+        assert!(get_vhost_config_file_list(unknown_path, &[".conf".to_string()], false).is_err());
     }
 
     #[test]
     fn include_vhosts_with_redirect_inside_location() {
         let vhost_file = Path::new("tests/nginx-vhosts/vhost2.conf");
-        let vhosts = get_virtual_hosts_from_file(
-            vhost_file, &get_nginx_vhost_section_start_regex(),
-            &get_nginx_redirect_with_301_regex(),
-            &get_nginx_vhost_port_regex(),
-            &get_domain_search_regex_for_nginx_vhost()
-        ).unwrap();
+        let vhosts = get_virtual_hosts_from_file(vhost_file).unwrap();
 
         let expected_domain = "goodhost.ru";
 
@@ -337,5 +306,78 @@ mod webserver_tests {
 
         assert_eq!(result.domain, expected_domain);
         assert_eq!(result.port, 443);
+        assert!(result.redirect.is_none());
+        assert_eq!(result.expected_status, Some(301));
+
+        let certbot_redirect = vhosts.iter().find(
+            |vhost| vhost.domain == expected_domain && vhost.port == 80
+        ).unwrap();
+
+        let redirect = certbot_redirect.redirect.as_ref().unwrap();
+        assert_eq!(redirect.status, 301);
+        assert_eq!(redirect.target, "https://goodhost.ru/");
+    }
+
+    #[test]
+    fn listen_address_formats_should_be_parsed() {
+        let cases = [
+            ("80", None, Some(80)),
+            ("*:8080", None, Some(8080)),
+            ("10.0.0.1:443", Some("10.0.0.1"), Some(443)),
+            ("10.0.0.1", Some("10.0.0.1"), None),
+            ("localhost:8000", Some("localhost"), Some(8000)),
+            ("[::]:443", Some("::"), Some(443)),
+            ("[2001:db8::1]:8080", Some("2001:db8::1"), Some(8080)),
+            ("[::1]", Some("::1"), None),
+            ("_default_:443", None, Some(443)),
+        ];
+
+        for (value, address, port) in cases {
+            assert_eq!(
+                parse_listen_address(value),
+                Some(ListenAddress { address: address.map(|a| a.to_string()), port }),
+                "value '{value}'"
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported_listen_address_should_be_skipped() {
+        for value in ["unix:/var/run/nginx.sock", "[::1", "[::1]8080", "2001:db8::1:80", "host:port", ""] {
+            assert_eq!(parse_listen_address(value), None, "value '{value}'");
+        }
+    }
+
+    #[test]
+    fn host_and_port_should_be_split() {
+        let cases = [
+            ("example.com", Some(("example.com", None))),
+            ("example.com:8080", Some(("example.com", Some(8080)))),
+            ("[2001:db8::1]:443", Some(("2001:db8::1", Some(443)))),
+            ("[::1]", Some(("::1", None))),
+            ("example.com:http", None),
+            ("[::1]8080", None),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(split_host_port(value), expected, "value '{value}'");
+        }
+    }
+
+    #[test]
+    fn domain_names_should_be_normalized() {
+        let cases = [
+            ("Example.COM", "example.com"),
+            ("ПРИМЕР.рф", "пример.рф"),
+            ("xn--e1afmkfd.xn--p1ai", "пример.рф"),
+            ("*.Example.com", "*.example.com"),
+            ("www.xn--e1afmkfd.*", "www.пример.*"),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(normalize_domain_name(name), Some(expected.to_string()), "name '{name}'");
+        }
+
+        assert_eq!(normalize_domain_name("xn--zz-bad.com"), None);
     }
 }